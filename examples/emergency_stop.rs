@@ -14,6 +14,7 @@ async fn fly() -> Result<()> {
     let drone = drone.connect().await?;
 
     drone.take_off().await?;
+    drone.arm_emergency_stop();
     drone.emergency_stop().await?; // warning! this will make the drone drop like a brick
 
     Ok(())
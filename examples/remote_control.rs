@@ -9,7 +9,8 @@
 //  - Left Stick: go forwards/backwards and turn left/right
 //  - Right Stick: up/down and strafe left/right
 //  - D-Pad: flip
-//  - Left Shoulder + Right Shoulder: emergency stop
+//  - Back (the one above the left stick): arm emergency stop for 3 seconds
+//  - Left Shoulder + Right Shoulder: emergency stop, once armed
 //
 // Note that the Tello drone will automatically land if forced down to ~20cm 
 // above a surface
@@ -29,11 +30,17 @@ use tello_edu::{TelloOptions, Tello, Result, TelloCommandSender, TelloCommand};
 fn main() {
     let mut options = TelloOptions::default();
 
-    // we want to send commands...
-    let command_sender = options.with_command();
+    // we want to send commands, and to stop cleanly when done...
+    let (command_sender, command_handle) = options.with_command_handle();
+
+    // ...and for the drone to keep moving smoothly while a stick is held
+    options.repeat_remote_control = true;
+
+    // ...without lagging behind the sticks if the drone is slow to respond
+    options.coalesce_remote_control = true;
 
     // run async Tokio runtime in a thread...
-    std::thread::spawn(move || {
+    let flying = std::thread::spawn(move || {
         let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
@@ -45,6 +52,10 @@ fn main() {
     });
 
     run_control(command_sender).expect("failed to run control");
+
+    // closing the window stops the command handling, so wait for it to finish
+    command_handle.shutdown();
+    flying.join().unwrap();
 }
 
 fn run_control(command_sender: TelloCommandSender) -> anyhow::Result<(),  String> {
@@ -94,7 +105,13 @@ fn run_control(command_sender: TelloCommandSender) -> anyhow::Result<(),  String
     for event in sdl_context.event_pump()?.wait_iter() {
 
         match event {
-            // both shoulder buttons together to immediately stop motors (and drop like a brick!)
+            // back button to arm the emergency stop for the next 3 seconds
+            Event::ControllerButtonDown { button: Button::Back, .. } => {
+                println!("emergency stop armed");
+                command_sender.send(TelloCommand::ArmEmergencyStop)
+            }
+
+            // both shoulder buttons together, once armed, to immediately stop motors (and drop like a brick!)
             Event::ControllerButtonDown { button: Button::LeftShoulder, .. } 
             | Event::ControllerButtonDown { button: Button::RightShoulder, .. } => {
                 if controller.button(Button::LeftShoulder) && controller.button(Button::RightShoulder) {
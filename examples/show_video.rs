@@ -57,6 +57,7 @@ fn run_gui(mut video_receiver:TelloVideoReceiver) -> std::result::Result<(), Str
 
     let mut video_channel_open = true;
     let mut decoder = Decoder::new().unwrap();
+    let mut next_sequence = 0;
 
     'running: loop {
         // SDL event loop
@@ -75,6 +76,11 @@ fn run_gui(mut video_receiver:TelloVideoReceiver) -> std::result::Result<(), Str
             // wait for next encoded frame of video
             match video_receiver.blocking_recv() {
                 Some(frame) => {
+                    if frame.sequence > next_sequence {
+                        println!("{} frames missed", frame.sequence - next_sequence);
+                    }
+                    next_sequence = frame.sequence + 1;
+
                     // decode h264 to YUV
                     match decoder.decode(&frame.data) {
                         Ok(Some(f)) =>  {
@@ -0,0 +1,75 @@
+/// A box the drone must stay inside, eg to keep it away from the walls 
+/// indoors. See `TelloOptions::with_bounds()`.
+///
+/// Coordinates are in cm relative to the take off point, with x forwards, 
+/// y to the left and z up, as faced when first taking off.
+///
+/// *nb* The position is estimated by adding up the moves and turns the 
+/// drone has been told to make, so is only approximate and will drift.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlightBounds {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+    pub min_z: f32,
+    pub max_z: f32
+}
+
+impl FlightBounds {
+    /// Whether the position is inside the bounds.
+    pub fn contains(&self, x: f32, y: f32, z: f32) -> bool {
+        (self.min_x..=self.max_x).contains(&x) &&
+        (self.min_y..=self.max_y).contains(&y) &&
+        (self.min_z..=self.max_z).contains(&z)
+    }
+}
+
+/// Dead-reckoned position of the drone, in the same coordinates as 
+/// `FlightBounds`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct PositionEstimate {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) z: f32,
+
+    /// Heading in degrees counter-clockwise from the initial heading.
+    pub(crate) heading: f32
+}
+
+impl PositionEstimate {
+    /// The position after moving the given distances relative to the 
+    /// drone's current heading.
+    pub(crate) fn moved(&self, forwards: f32, left: f32, up: f32) -> Self {
+        let (sin, cos) = self.heading.to_radians().sin_cos();
+        Self {
+            x: self.x + forwards * cos - left * sin,
+            y: self.y + forwards * sin + left * cos,
+            z: self.z + up,
+            heading: self.heading
+        }
+    }
+
+    /// The position after turning counter-clockwise by the given angle.
+    pub(crate) fn turned(&self, counterclockwise_degrees: f32) -> Self {
+        Self { heading: (self.heading + counterclockwise_degrees) % 360.0, ..*self }
+    }
+
+    /// Whether the position is inside the bounds.
+    pub(crate) fn is_inside(&self, bounds: &FlightBounds) -> bool {
+        bounds.contains(self.x, self.y, self.z)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flight_bounds_round_trip_through_json() {
+        let bounds = FlightBounds { min_x: -200.0, max_x: 300.5, min_y: -150.0, max_y: 150.0, min_z: 0.0, max_z: 250.0 };
+        let json = serde_json::to_string(&bounds).unwrap();
+        assert_eq!(serde_json::from_str::<FlightBounds>(&json).unwrap(), bounds);
+    }
+}
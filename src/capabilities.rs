@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use crate::errors::{Result, TelloError};
+
+/// Optional features which not all drones or firmware versions support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Mission pad detection and the commands using it (Tello EDU, SDK 2.0+)
+    MissionPads,
+
+    /// Joining an existing WiFi network (SDK 2.0+)
+    StationMode,
+
+    /// Setting the video bitrate, resolution and frame rate (SDK 3.0+)
+    VideoConfig,
+
+    /// The RoboMaster TT expansion board, controlled with `EXT` commands
+    ExtExpansion
+}
+
+impl Capability {
+    /// The features a drone with the given SDK version, as returned by 
+    /// `Tello::sdk_version()`, should support.
+    ///
+    /// ```
+    /// use tello_edu::Capability;
+    ///
+    /// assert!(Capability::for_sdk_version("1.3").unwrap().is_empty());
+    ///
+    /// let edu = Capability::for_sdk_version("20").unwrap();
+    /// assert!(edu.contains(&Capability::MissionPads));
+    /// assert!(!edu.contains(&Capability::VideoConfig));
+    ///
+    /// let talent = Capability::for_sdk_version("30").unwrap();
+    /// assert!(talent.contains(&Capability::ExtExpansion));
+    ///
+    /// assert!(Capability::for_sdk_version("unknown").is_err());
+    /// ```
+    pub fn for_sdk_version(version: &str) -> Result<HashSet<Capability>> {
+        let version = parse_sdk_version(version)?;
+
+        let mut capabilities = HashSet::new();
+        if version >= (2, 0) {
+            capabilities.insert(Capability::MissionPads);
+            capabilities.insert(Capability::StationMode);
+        }
+        if version >= (3, 0) {
+            capabilities.insert(Capability::VideoConfig);
+            capabilities.insert(Capability::ExtExpansion);
+        }
+        Ok(capabilities)
+    }
+}
+
+/// Parses an SDK version as returned by `sdk?` to `(major, minor)`, 
+/// accepting both the dotted form eg "1.3" and the undotted eg "20".
+pub(crate) fn parse_sdk_version(version: &str) -> Result<(u8, u8)> {
+    let version = version.trim();
+    let parse_error = || TelloError::ParseError { msg: format!("unexpected SDK version \"{version}\"") };
+    let digit = |c: char| c.to_digit(10).map(|d| d as u8).ok_or_else(parse_error);
+
+    match version.split_once('.') {
+        Some((major, minor)) => {
+            let major = major.parse::<u8>().map_err(|_| parse_error())?;
+            let minor = minor.parse::<u8>().map_err(|_| parse_error())?;
+            Ok((major, minor))
+        }
+        None => {
+            let mut chars = version.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(major), None, None) => Ok((digit(major)?, 0)),
+                (Some(major), Some(minor), None) => Ok((digit(major)?, digit(minor)?)),
+                _ => Err(parse_error())
+            }
+        }
+    }
+}
@@ -1,16 +1,116 @@
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 
-#[derive(Debug)]
+use crate::errors::{Result, TelloError};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TelloCommand {
     TakeOff,
     Land,
     StopAndHover,
+    /// Stop all motors immediately, if armed with `ArmEmergencyStop` in the
+    /// last 3 seconds, as `Tello::emergency_stop()`. Otherwise it is 
+    /// ignored, with a warning.
     EmergencyStop,
+    /// Arm `EmergencyStop`, as `Tello::arm_emergency_stop()`. Nothing is 
+    /// sent to the drone.
+    ArmEmergencyStop,
     RemoteControl { left_right: i8, forwards_backwards: i8, up_down: i8, yaw: i8 },
     FlipLeft,
     FlipRight,
     FlipForward,
-    FlipBack
+    FlipBack,
+    /// Move up, 20-500 cm
+    MoveUp(u16),
+    /// Move down, 20-500 cm
+    MoveDown(u16),
+    /// Move forward, 20-500 cm
+    MoveForward(u16),
+    /// Move back, 20-500 cm
+    MoveBack(u16),
+    /// Move left, 20-500 cm
+    MoveLeft(u16),
+    /// Move right, 20-500 cm
+    MoveRight(u16),
+    /// Turn clockwise, 1-360°
+    TurnClockwise(u16),
+    /// Turn counter-clockwise, 1-360°
+    TurnCounterClockwise(u16),
+    /// Set the speed, 10-100 cm/s
+    SetSpeed(u8)
+}
+
+impl TelloCommand {
+    /// The command as sent to the drone, eg `flip l`, or an empty string 
+    /// for `ArmEmergencyStop`, which isn't sent.
+    pub fn to_sdk_string(&self) -> String {
+        match self {
+            TelloCommand::TakeOff => "takeoff".to_string(),
+            TelloCommand::Land => "land".to_string(),
+            TelloCommand::StopAndHover => "stop".to_string(),
+            TelloCommand::EmergencyStop => "emergency".to_string(),
+            TelloCommand::ArmEmergencyStop => String::new(),
+            TelloCommand::RemoteControl { left_right, forwards_backwards, up_down, yaw } => 
+                format!("rc {left_right} {forwards_backwards} {up_down} {yaw}"),
+            TelloCommand::FlipLeft => "flip l".to_string(),
+            TelloCommand::FlipRight => "flip r".to_string(),
+            TelloCommand::FlipForward => "flip f".to_string(),
+            TelloCommand::FlipBack => "flip b".to_string(),
+            TelloCommand::MoveUp(distance) => format!("up {distance}"),
+            TelloCommand::MoveDown(distance) => format!("down {distance}"),
+            TelloCommand::MoveForward(distance) => format!("forward {distance}"),
+            TelloCommand::MoveBack(distance) => format!("back {distance}"),
+            TelloCommand::MoveLeft(distance) => format!("left {distance}"),
+            TelloCommand::MoveRight(distance) => format!("right {distance}"),
+            TelloCommand::TurnClockwise(degrees) => format!("cw {degrees}"),
+            TelloCommand::TurnCounterClockwise(degrees) => format!("ccw {degrees}"),
+            TelloCommand::SetSpeed(speed) => format!("speed {speed}")
+        }
+    }
+
+    /// Parses a command as sent to the drone, the reverse of 
+    /// `to_sdk_string()`.
+    ///
+    /// ```
+    /// use tello_edu::TelloCommand;
+    ///
+    /// let command = TelloCommand::from_sdk_string("rc 0 50 0 -10").unwrap();
+    /// assert_eq!(command.to_sdk_string(), "rc 0 50 0 -10");
+    /// ```
+    pub fn from_sdk_string(s: &str) -> Result<TelloCommand> {
+        let parse_error = || TelloError::ParseError { msg: format!("unknown command \"{s}\"") };
+        let value = |v: &str| v.parse::<i8>().map_err(|_| parse_error());
+        let amount = |v: &str| v.parse::<u16>().map_err(|_| parse_error());
+
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["takeoff"] => Ok(TelloCommand::TakeOff),
+            ["land"] => Ok(TelloCommand::Land),
+            ["stop"] => Ok(TelloCommand::StopAndHover),
+            ["emergency"] => Ok(TelloCommand::EmergencyStop),
+            ["rc", left_right, forwards_backwards, up_down, yaw] => Ok(TelloCommand::RemoteControl { 
+                left_right: value(left_right)?, 
+                forwards_backwards: value(forwards_backwards)?, 
+                up_down: value(up_down)?, 
+                yaw: value(yaw)? 
+            }),
+            ["flip", "l"] => Ok(TelloCommand::FlipLeft),
+            ["flip", "r"] => Ok(TelloCommand::FlipRight),
+            ["flip", "f"] => Ok(TelloCommand::FlipForward),
+            ["flip", "b"] => Ok(TelloCommand::FlipBack),
+            ["up", distance] => Ok(TelloCommand::MoveUp(amount(distance)?)),
+            ["down", distance] => Ok(TelloCommand::MoveDown(amount(distance)?)),
+            ["forward", distance] => Ok(TelloCommand::MoveForward(amount(distance)?)),
+            ["back", distance] => Ok(TelloCommand::MoveBack(amount(distance)?)),
+            ["left", distance] => Ok(TelloCommand::MoveLeft(amount(distance)?)),
+            ["right", distance] => Ok(TelloCommand::MoveRight(amount(distance)?)),
+            ["cw", degrees] => Ok(TelloCommand::TurnClockwise(amount(degrees)?)),
+            ["ccw", degrees] => Ok(TelloCommand::TurnCounterClockwise(amount(degrees)?)),
+            ["speed", speed] => Ok(TelloCommand::SetSpeed(speed.parse::<u8>().map_err(|_| parse_error())?)),
+            _ => Err(parse_error())
+        }
+    }
 }
 
 
@@ -21,3 +121,83 @@ pub fn make_tello_command_channel() -> (TelloCommandSender, TelloCommandReceiver
     mpsc::unbounded_channel()
 }
 
+/// The outcome of a command from the command channel, see 
+/// `TelloOptions::with_command_results()`.
+#[derive(Debug)]
+pub struct TelloCommandResult {
+    pub command: TelloCommand,
+    pub result: Result<()>
+}
+
+pub type TelloCommandResultSender = mpsc::UnboundedSender<TelloCommandResult>;
+pub type TelloCommandResultReceiver = mpsc::UnboundedReceiver<TelloCommandResult>;
+
+pub fn make_tello_command_result_channel() -> (TelloCommandResultSender, TelloCommandResultReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// Every command sent to the drone, with the time it was sent since 
+/// connecting, see `TelloOptions::with_command_log()`.
+pub type CommandLog = Arc<std::sync::Mutex<Vec<(Duration, String)>>>;
+
+/// Handle for stopping command handling, and waiting until it has finished.
+#[derive(Debug)]
+pub struct TelloCommandHandle {
+    shutdown_tx: watch::Sender<bool>,
+    finished_rx: watch::Receiver<bool>
+}
+
+impl TelloCommandHandle {
+    /// Ask `handle_commands` to stop, even if no commands are waiting.
+    pub fn shutdown(&self) {
+        let _ = self.shutdown_tx.send(true);
+    }
+
+    /// Wait until `handle_commands` has finished, whether because of 
+    /// `shutdown`, the command channel closing, or an error.
+    pub async fn finished(&mut self) {
+        while !*self.finished_rx.borrow() {
+            if self.finished_rx.changed().await.is_err() {
+                // drone dropped, so nothing left to wait for
+                break;
+            }
+        }
+    }
+}
+
+/// The drone's end of a `TelloCommandHandle`.
+#[derive(Debug)]
+pub(crate) struct CommandControl {
+    shutdown_rx: watch::Receiver<bool>,
+    finished_tx: watch::Sender<bool>
+}
+
+impl CommandControl {
+    /// Resolves when shutdown has been requested, never if the handle has 
+    /// been dropped without asking.
+    pub(crate) async fn shutdown_requested(&self) {
+        let mut rx = self.shutdown_rx.clone();
+        loop {
+            if *rx.borrow() {
+                return;
+            }
+            if rx.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    pub(crate) fn is_shutdown_requested(&self) -> bool {
+        *self.shutdown_rx.borrow()
+    }
+
+    pub(crate) fn set_finished(&self) {
+        let _ = self.finished_tx.send(true);
+    }
+}
+
+pub(crate) fn make_tello_command_handle() -> (TelloCommandHandle, CommandControl) {
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let (finished_tx, finished_rx) = watch::channel(false);
+    (TelloCommandHandle { shutdown_tx, finished_rx }, CommandControl { shutdown_rx, finished_tx })
+}
@@ -0,0 +1,39 @@
+/// Which way to move, see `Tello::move_in()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Forward,
+    Back
+}
+
+impl Direction {
+    /// The SDK command for moving this way.
+    pub(crate) fn keyword(&self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Forward => "forward",
+            Direction::Back => "back"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords() {
+        assert_eq!(Direction::Up.keyword(), "up");
+        assert_eq!(Direction::Down.keyword(), "down");
+        assert_eq!(Direction::Left.keyword(), "left");
+        assert_eq!(Direction::Right.keyword(), "right");
+        assert_eq!(Direction::Forward.keyword(), "forward");
+        assert_eq!(Direction::Back.keyword(), "back");
+    }
+}
@@ -26,7 +26,40 @@ pub enum TelloError {
 	OutOfRange,
 
 	#[error("Non-specific error response")]
-	NonSpecificError
+	NonSpecificError,
+
+	#[error("Timed out waiting for a response to \"{command}\"")]
+	Timeout { command: String },
+
+	#[error("State updates are required, but were not requested when connecting")]
+	StateNotEnabled,
+
+	#[error("The drone is overheating")]
+	Overheating,
+
+	#[error("Drone SDK version {version} is older than the required {required}")]
+	UnsupportedFirmware { version: String, required: String },
+
+	#[error("Move would take the drone outside its flight bounds")]
+	OutOfBounds,
+
+	#[error("The motors have stopped")]
+	MotorStop,
+
+	#[error("The drone is not under joystick control")]
+	NotJoystick,
+
+	#[error("The drone is landing automatically, eg because of low battery")]
+	AutoLand,
+
+	#[error("No valid IMU reading, eg the drone is not on a flat surface")]
+	NoValidImu,
+
+	#[error("Lost the link to the drone, and failed to reconnect after {attempts} attempts")]
+	ConnectionLost { attempts: u32 },
+
+	#[error("The host has left the drone's WiFi network")]
+	WiFiLost
 }
 
 impl From<std::io::Error> for TelloError {
@@ -42,10 +75,62 @@ impl From<std::string::FromUtf8Error> for TelloError {
 }
 
 impl TelloError {
+	/// Whether the error is likely to be transient, so retrying the 
+	/// operation may succeed, as opposed to fatal, where it won't without 
+	/// changing something first.
+	///
+	/// Recoverable:
+	/// - `IOError`, `DecodeError`, `ParseError` - eg a dropped or garbled message
+	/// - `Timeout` - no response arrived in time
+	/// - `NonSpecificError` - the drone often responds "error" when busy
+	/// - `Overheating` - once the drone has cooled down
+	/// - `NoValidImu` - eg once the drone has been put down level
+	/// - `WiFiLost` - once the host rejoins the drone's network
+	///
+	/// Everything else is fatal.
+	///
+	/// ```
+	/// use tello_edu::TelloError;
+	///
+	/// assert!(TelloError::WiFiLost.is_recoverable());
+	/// assert!(!TelloError::ConnectionLost { attempts: 3 }.is_recoverable());
+	/// ```
+	pub fn is_recoverable(&self) -> bool {
+		matches!(self, 
+			TelloError::IOError { .. } |
+			TelloError::DecodeError { .. } |
+			TelloError::ParseError { .. } |
+			TelloError::Timeout { .. } |
+			TelloError::NonSpecificError |
+			TelloError::Overheating |
+			TelloError::NoValidImu |
+			TelloError::WiFiLost
+		)
+	}
+
+	/// The error for a response other than "ok".
+	///
+	/// ```
+	/// use tello_edu::TelloError;
+	///
+	/// let error = |response: &str| TelloError::from_not_ok_response(response.to_string());
+	///
+	/// assert!(matches!(error("error"), TelloError::NonSpecificError));
+	/// assert!(matches!(error("out of range"), TelloError::OutOfRange));
+	/// assert!(matches!(error("error Motor stop"), TelloError::MotorStop));
+	/// assert!(matches!(error("error Not joystick"), TelloError::NotJoystick));
+	/// assert!(matches!(error("error Auto land"), TelloError::AutoLand));
+	/// assert!(matches!(error("error No valid imu"), TelloError::NoValidImu));
+	/// assert!(matches!(error("error Something new"), TelloError::NotOkResponse { .. }));
+	/// ```
 	pub fn from_not_ok_response(response: String) -> TelloError {
-		match response.as_str() {
+		match response.to_ascii_lowercase().as_str() {
 			"error" => TelloError::NonSpecificError,
 			"out of range" => TelloError::OutOfRange,
+			"error motor stop" => TelloError::MotorStop,
+			"error not joystick" => TelloError::NotJoystick,
+			"error auto land" => TelloError::AutoLand,
+			"error no valid imu" => TelloError::NoValidImu,
 			_ => TelloError::NotOkResponse { response }
 		}
 	}
@@ -0,0 +1,37 @@
+use tokio::sync::mpsc;
+
+/// Progress of the connection to the drone and the messages exchanged 
+/// with it, see `TelloOptions::with_events()`.
+///
+/// These mirror what's logged, for when eg a GUI wants to show it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TelloEvent {
+    /// Started connecting.
+    Connecting,
+
+    /// Binding the local socket or connecting failed, and is being tried 
+    /// again.
+    ConnectRetry { attempt: u32 },
+
+    /// Connected, and the drone is ready for commands.
+    Connected,
+
+    /// The battery was low when connecting.
+    LowBattery { percent: u8 },
+
+    /// A command was sent, with any WiFi password masked.
+    CommandSent { command: String },
+
+    /// A response was received.
+    ResponseReceived { response: String },
+
+    /// Disconnected.
+    Disconnected
+}
+
+pub type TelloEventSender = mpsc::UnboundedSender<TelloEvent>;
+pub type TelloEventReceiver = mpsc::UnboundedReceiver<TelloEvent>;
+
+pub fn make_tello_event_channel() -> (TelloEventSender, TelloEventReceiver) {
+    mpsc::unbounded_channel()
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use tokio::{spawn, task};
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use log::{debug, info, warn};
+
+/// Held while a command is sent and its response received, so commands 
+/// from different tasks never interleave. Holds the time the last command 
+/// finished.
+pub(crate) type CommandLock = Arc<Mutex<Instant>>;
+
+/// The drone lands itself after 15s without a command, so one is sent 
+/// after this long.
+const KEEP_ALIVE_INTERVAL:Duration = Duration::from_secs(5);
+
+/// How often to check whether a keep alive is due.
+const KEEP_ALIVE_CHECK_INTERVAL:Duration = Duration::from_secs(1);
+
+/// How long to wait for the drone to acknowledge a keep alive.
+const KEEP_ALIVE_TIMEOUT:Duration = Duration::from_secs(1);
+
+/// Sends `command` whenever no other command has been sent for a while, 
+/// so the drone doesn't land itself, see `TelloOptions::keep_alive`.
+#[derive(Debug)]
+pub(crate) struct KeepAlive {
+    task: task::JoinHandle<()>
+}
+
+impl KeepAlive {
+    pub(crate) fn start(sock: Arc<UdpSocket>, command_lock: CommandLock) -> Self {
+        info!("[Tello] START KEEP ALIVE");
+
+        let task = spawn(async move {
+            loop {
+                sleep(KEEP_ALIVE_CHECK_INTERVAL).await;
+
+                let mut last_command = command_lock.lock().await;
+                if last_command.elapsed() < KEEP_ALIVE_INTERVAL {
+                    continue;
+                }
+
+                debug!("[Tello] SEND command (keep alive)");
+                if let Err(err) = sock.send(b"command").await {
+                    warn!("[Tello] failed to send keep alive ({err})");
+                    continue;
+                }
+
+                // read the "ok", so it isn't taken as the response to the 
+                // next command
+                let mut buf = vec![0; 256];
+                if timeout(KEEP_ALIVE_TIMEOUT, sock.recv(&mut buf)).await.is_err() {
+                    warn!("[Tello] WARNING keep alive was not acknowledged");
+                }
+
+                *last_command = Instant::now();
+            }
+        });
+
+        Self { task }
+    }
+
+    /// Stops without waiting, eg when the drone is dropped.
+    pub(crate) fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub(crate) async fn stop(self) {
+        info!("[Tello] STOP KEEP ALIVE");
+        self.task.abort();
+        let _ = self.task.await;
+    }
+}
@@ -32,6 +32,11 @@
 //! }
 //! ```
 //! 
+//! Progress is logged with the [log](https://docs.rs/log) crate - connection
+//! and lifecycle at info level, each command sent and response received at 
+//! debug, and problems such as low battery at warn. Install a logger (eg 
+//! [env_logger](https://docs.rs/env_logger)) to see it.
+//! 
 
 mod errors;
 mod wifi;
@@ -40,12 +45,40 @@ mod state;
 mod options;
 mod video;
 mod command;
+mod telemetry;
+mod bounds;
+mod safety;
+mod capabilities;
+mod reconnect;
+mod mission_pads;
+mod direction;
+mod keep_alive;
+mod recv_backoff;
+mod sdk;
+mod remote_control;
+mod events;
+#[cfg(feature = "recording")]
+mod recording;
+#[cfg(feature = "image")]
+mod snapshot;
 
 pub use errors::{TelloError, Result};
-pub use tello::Tello;
-pub use options::TelloOptions;
-pub use state::{TelloStateReceiver, TelloState};
-pub use video::{VIDEO_WIDTH, VIDEO_HEIGHT, TelloVideoReceiver};
-pub use command::{TelloCommandSender, TelloCommand};
+pub use tello::{Tello, NoWifi, Disconnected, Connected};
+pub use options::{TelloOptions, TelloOptionsBuilder, TelloHandles, SmallMovePolicy};
+pub use state::{TelloStateReceiver, TelloStateWatch, TelloState, Vector3};
+pub use video::{VIDEO_WIDTH, VIDEO_HEIGHT, TelloVideoReceiver, VideoStats, VideoBitrate, VideoResolution, VideoFps, record_h264};
+pub use command::{TelloCommandSender, TelloCommand, TelloCommandHandle, TelloCommandResult, TelloCommandResultReceiver, CommandLog};
+pub use telemetry::Telemetry;
+pub use bounds::FlightBounds;
+pub use safety::install_panic_safety;
+pub use capabilities::Capability;
+pub use reconnect::ReconnectPolicy;
+pub use mission_pads::MissionPadDirection;
+pub use direction::Direction;
+pub use sdk::{SdkCommand, SdkResponse};
+pub use wifi::{WiFiEvent, WiFiEventReceiver};
+pub use events::{TelloEvent, TelloEventReceiver};
+#[cfg(feature = "recording")]
+pub use recording::record_video_and_state;
 
 pub use tokio::time::Duration;
\ No newline at end of file
@@ -0,0 +1,25 @@
+/// Which camera(s) the drone uses to look for mission pads, see 
+/// `Tello::set_mission_pad_detection_direction()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MissionPadDirection {
+    /// Look down only, the default, at 20Hz.
+    #[default]
+    Downward,
+
+    /// Look forwards only, at 20Hz.
+    Forward,
+
+    /// Look both down and forwards, alternating at 10Hz each.
+    Both
+}
+
+impl MissionPadDirection {
+    /// The value for the `mdirection` command.
+    pub(crate) fn value(&self) -> u8 {
+        match self {
+            MissionPadDirection::Downward => 0,
+            MissionPadDirection::Forward => 1,
+            MissionPadDirection::Both => 2
+        }
+    }
+}
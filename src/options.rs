@@ -1,16 +1,300 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
 use crate::state::*;
 use crate::video::*;
 use crate::command::*;
+use crate::bounds::FlightBounds;
+use crate::reconnect::ReconnectPolicy;
+use crate::wifi::{WiFiEventSender, WiFiEventReceiver, make_wifi_event_channel};
+use crate::events::{TelloEvent, TelloEventSender, TelloEventReceiver, make_tello_event_channel};
+
+/// What to do with moves shorter than the 20cm the drone accepts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SmallMovePolicy {
+    /// Fail with `TelloError::OutOfRange`, as the drone would, without 
+    /// sending the move.
+    #[default]
+    Reject,
+
+    /// Move the minimum 20cm instead.
+    ClampUp,
+
+    /// Don't move at all, just log a warning.
+    Ignore
+}
 
 /// Tello drone connection and other usage options.
-#[derive(Default)]
+///
+/// Clones share the same channels, so options kept from 
+/// `Tello::disconnect_keeping_options()` can be used to connect again 
+/// without setting up new channels.
+#[derive(Debug, Clone)]
 pub struct TelloOptions {
     pub(crate) state_sender: Option<TelloStateSender>,
+    pub(crate) state_watch_sender: Option<TelloStateWatchSender>,
     pub(crate) video_sender: Option<TelloVideoSender>,
-    pub(crate) command_receiver: Option<TelloCommandReceiver>
+    pub(crate) command_receiver: Option<Arc<Mutex<TelloCommandReceiver>>>,
+    pub(crate) command_control: Option<Arc<CommandControl>>,
+    pub(crate) command_result_sender: Option<TelloCommandResultSender>,
+    pub(crate) wifi_event_sender: Option<WiFiEventSender>,
+    pub(crate) command_log: Option<CommandLog>,
+    pub(crate) event_sender: Option<TelloEventSender>,
+    pub(crate) telemetry_csv_path: Option<PathBuf>,
+    pub(crate) bounds: Option<FlightBounds>,
+    pub(crate) auto_reconnect: Option<ReconnectPolicy>,
+
+    /// The local UDP port commands are sent from, and responses received 
+    /// on, default 8889. Only changed by tests, so several can run at once.
+    pub(crate) local_control_port: u16,
+
+    /// Decode responses that are not valid UTF-8 lossily (with a warning)
+    /// rather than failing with `TelloError::DecodeError`. Off by default.
+    pub lossy_responses: bool,
+
+    /// How to handle moves shorter than the minimum 20cm, eg small 
+    /// corrections from a control loop.
+    pub small_moves: SmallMovePolicy,
+
+    /// Don't send `command` to put the drone in command mode when 
+    /// connecting, eg when it is already in command mode from an earlier 
+    /// session.
+    pub skip_command_handshake: bool,
+
+    /// Refuse to flip, with `TelloError::Overheating`, while the drone is 
+    /// overheating. Requires state updates.
+    pub block_flips_when_overheating: bool,
+
+    /// Fail to connect, with `TelloError::UnsupportedFirmware`, if the 
+    /// drone's SDK version is older than this `(major, minor)` version, eg
+    /// `Some((2, 0))` for mission pads.
+    pub require_sdk_version: Option<(u8, u8)>,
+
+    /// Cross-check moves against the drone's velocity, warning if eg a move
+    /// times out while the drone is still moving. Requires state updates.
+    pub confirm_moves: bool,
+
+    /// Size in bytes of the OS receive buffer for the video socket, or 
+    /// `None` for the OS default. The default is too small on some systems,
+    /// so packets are dropped and frames corrupted - around 1MB 
+    /// (`Some(1024 * 1024)`) is usually plenty.
+    ///
+    /// *nb* On Linux this is capped at `net.core.rmem_max`
+    pub video_receive_buffer_size: Option<usize>,
+
+    /// Pass video frames on through a channel holding at most this many, 
+    /// dropping new frames (with a warning) while it's full rather than 
+    /// using more and more memory, eg `Some(4)` for a live preview. 
+    /// Default `None` for an unlimited channel.
+    ///
+    /// *nb* Must be set before calling `with_video()`
+    pub video_channel_capacity: Option<usize>,
+
+    /// Find out which optional features the drone supports when connecting,
+    /// see `Tello::supports()`. This makes connecting take a little longer.
+    pub probe_capabilities: bool,
+
+    /// How long `Tello::send()`, and so every command, waits for the 
+    /// drone's response before failing with `TelloError::Timeout`, eg if the
+    /// response was lost over flaky WiFi. Default 7s.
+    ///
+    /// *nb* Long moves can take longer than this - use the `_timeout` 
+    /// variants, eg `Tello::move_forward_timeout()`, to allow more time.
+    pub command_timeout: Duration,
+
+    /// Send a harmless command whenever no other has been sent for 5s, as
+    /// otherwise the drone lands itself after 15s, eg while a vision loop 
+    /// is busy between moves. Stopped on disconnect.
+    pub keep_alive: bool,
+
+    /// Keep re-sending the latest `Tello::remote_control()` values, 20 
+    /// times a second by default, until they're all zero or another command
+    /// is sent. The drone expects a steady stream of remote control updates,
+    /// and can twitch or stop when they come from eg a game controller 
+    /// which only sends events when a stick moves. See also 
+    /// `Tello::set_remote_control_repeat_rate()`.
+    pub repeat_remote_control: bool,
+
+    /// When `Tello::handle_commands()` falls behind, replace a waiting 
+    /// `TelloCommand::RemoteControl` with the next one rather than queueing
+    /// both. Stale stick positions are then skipped instead of played back
+    /// late, so a game controller stays responsive even while the drone is 
+    /// slow to acknowledge eg a flip.
+    pub coalesce_remote_control: bool,
+
+    /// When the connected drone is dropped while flying, eg if the flight 
+    /// script panics or returns early, send `rc 0 0 0 0` so it at least 
+    /// stops moving rather than carrying on until it lands itself. Default 
+    /// `true`.
+    ///
+    /// *nb* This is best effort only - it can't wait for a response, and 
+    /// the drone will not land.
+    pub stop_on_drop: bool,
+
+    /// Address of the drone, default "192.168.10.1" as in AP mode, ie using
+    /// the drone's own WiFi network. In station mode (see 
+    /// `Tello::join_access_point()`) it's whatever the router assigns.
+    ///
+    /// Pointing this at a stand-in on the local machine allows testing 
+    /// without a drone, eg
+    ///
+    /// ```
+    /// use tello_edu::{Tello, TelloOptions, TelloError, Result};
+    /// use tokio::net::UdpSocket;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// // a pretend drone, with canned responses
+    /// let mock = UdpSocket::bind("127.0.0.1:0").await?;
+    /// let mock_port = mock.local_addr()?.port();
+    /// tokio::spawn(async move {
+    ///     let mut buf = [0; 256];
+    ///     while let Ok((n, from)) = mock.recv_from(&mut buf).await {
+    ///         let responses: &[&str] = match &buf[..n] {
+    ///             b"battery?" => &["87"],
+    ///             b"stop" => &["forced stop", "ok"],
+    ///             b"flip l" => &["error Motor stop"],
+    ///             _ => &["ok"]
+    ///         };
+    ///         for response in responses {
+    ///             mock.send_to(response.as_bytes(), from).await.unwrap();
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// let mut options = TelloOptions::default();
+    /// options.drone_host = "127.0.0.1".to_string();
+    /// options.control_port = mock_port;
+    /// options.local_interface = Some([127, 0, 0, 1].into());
+    ///
+    /// let drone = Tello::new().assume_wifi().await?.connect_with(options).await?;
+    ///
+    /// drone.send_expect_ok("takeoff").await?;
+    /// assert_eq!(drone.send_expect::<u8>("battery?").await?, 87);
+    /// drone.stop().await?;
+    /// assert!(matches!(drone.flip_left().await, Err(TelloError::MotorStop)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub drone_host: String,
+
+    /// The drone's UDP port for commands, default 8889.
+    pub control_port: u16,
+
+    /// The local UDP port state updates are received on, default 8890. 
+    /// Only change this if the drone has already been told to send them 
+    /// elsewhere, eg with `Tello::set_ports()` in an earlier session - the 
+    /// options kept by `Tello::disconnect_keeping_options()` are updated 
+    /// to match.
+    pub state_port: u16,
+
+    /// The local UDP port video is received on, default 11111, see 
+    /// `state_port`.
+    pub video_port: u16,
+
+    /// The address of the local network interface to use, eg with one WiFi 
+    /// adapter per drone. Default `None` for any interface, ie 0.0.0.0.
+    ///
+    /// *nb* State and video are still broadcast by the drone, but only 
+    /// received through this interface.
+    pub local_interface: Option<IpAddr>
+}
+
+pub(crate) const DEFAULT_DRONE_HOST:&str = "192.168.10.1";
+
+pub(crate) const DEFAULT_CONTROL_UDP_PORT:u16 = 8889;
+
+pub(crate) const DEFAULT_LOCAL_CONTROL_UDP_PORT:u16 = 8889;
+
+/// Default time to wait for a response to a command.
+const DEFAULT_COMMAND_TIMEOUT:Duration = Duration::from_secs(7);
+
+impl Default for TelloOptions {
+    fn default() -> Self {
+        Self {
+            state_sender: None,
+            state_watch_sender: None,
+            video_sender: None,
+            command_receiver: None,
+            command_control: None,
+            command_result_sender: None,
+            wifi_event_sender: None,
+            command_log: None,
+            event_sender: None,
+            telemetry_csv_path: None,
+            bounds: None,
+            auto_reconnect: None,
+            local_control_port: DEFAULT_LOCAL_CONTROL_UDP_PORT,
+            lossy_responses: false,
+            small_moves: SmallMovePolicy::default(),
+            skip_command_handshake: false,
+            block_flips_when_overheating: false,
+            require_sdk_version: None,
+            confirm_moves: false,
+            video_receive_buffer_size: None,
+            video_channel_capacity: None,
+            probe_capabilities: false,
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
+            keep_alive: false,
+            repeat_remote_control: false,
+            coalesce_remote_control: false,
+            stop_on_drop: true,
+            drone_host: DEFAULT_DRONE_HOST.to_string(),
+            control_port: DEFAULT_CONTROL_UDP_PORT,
+            state_port: DEFAULT_STATE_UDP_PORT,
+            video_port: DEFAULT_VIDEO_UDP_PORT,
+            local_interface: None
+        }
+    }
 }
 
 impl TelloOptions {
+    /// Start building options in a single expression, eg
+    ///
+    /// ```
+    /// use tello_edu::{TelloOptions, Duration};
+    ///
+    /// let (options, handles) = TelloOptions::builder()
+    ///     .drone_host("192.168.1.42")
+    ///     .command_timeout(Duration::from_secs(10))
+    ///     .keep_alive(true)
+    ///     .with_state()
+    ///     .build();
+    ///
+    /// assert_eq!(options.drone_host, "192.168.1.42");
+    /// assert!(handles.state_receiver.is_some());
+    /// ```
+    pub fn builder() -> TelloOptionsBuilder {
+        TelloOptionsBuilder::default()
+    }
+
+    /// The local address to bind a socket to, on `local_interface` if set.
+    pub(crate) fn local_address(&self, port: u16) -> SocketAddr {
+        SocketAddr::new(self.local_interface.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)), port)
+    }
+
+    /// Passes on an event, if they were requested.
+    pub(crate) fn emit(&self, event: TelloEvent) {
+        if let Some(tx) = &self.event_sender {
+            // nothing to do if the receiver has gone
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Returns the receiver end of a channel for events such as connecting 
+    /// and each command sent, eg to show progress in a GUI.
+    ///
+    pub fn with_events(&mut self) -> TelloEventReceiver {
+        let (tx, rx) = make_tello_event_channel();
+        self.event_sender = Some(tx);
+        rx
+    }
+
     /// Request state updates from the drone.
     ///
     /// *nb* As messages are sent to the UDP broadcast address 0.0.0.0 this 
@@ -24,6 +308,57 @@ impl TelloOptions {
         rx
     }
 
+    /// As `with_state()`, but returns the updates as a `Stream`, eg to use 
+    /// the combinators in `tokio_stream::StreamExt`.
+    ///
+    /// ```no_run
+    /// # use tello_edu::{Tello, TelloOptions, Result, Duration};
+    /// use tokio_stream::StreamExt;
+    ///
+    /// # async fn print_state() -> Result<()> {
+    /// let mut options = TelloOptions::default();
+    ///
+    /// // no more than 10 updates a second
+    /// let states = options.with_state_stream().throttle(Duration::from_millis(100));
+    /// tokio::pin!(states);
+    ///
+    /// let drone = Tello::new().wait_for_wifi().await?.connect_with(options).await?;
+    ///
+    /// while let Some(state) = states.next().await {
+    ///     println!("height {}cm", state.height);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_state_stream(&mut self) -> UnboundedReceiverStream<TelloState> {
+        UnboundedReceiverStream::new(self.with_state())
+    }
+
+    /// As `with_state()`, but only ever keeps the latest state rather than 
+    /// queueing every update, for when you just want to know the state right
+    /// now, eg in a control loop, with `borrow()`. Can be used alongside 
+    /// `with_state()`.
+    ///
+    /// ```no_run
+    /// # use tello_edu::{Tello, TelloOptions, Result};
+    /// # async fn watch() -> Result<()> {
+    /// let mut options = TelloOptions::default();
+    /// let state_watch = options.with_state_watch();
+    ///
+    /// let drone = Tello::new().wait_for_wifi().await?.connect_with(options).await?;
+    ///
+    /// if let Some(state) = state_watch.borrow().as_ref() {
+    ///     println!("height: {}cm", state.height);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_state_watch(&mut self) -> TelloStateWatch {
+        let (tx, rx) = make_tello_state_watch();
+        self.state_watch_sender = Some(tx);
+        rx
+    }
+
     /// Request video from the drone as a stream of h264-encoded 720p YUV 
     /// frames.
     ///
@@ -33,17 +368,268 @@ impl TelloOptions {
     /// Returns the receiver end of the channel used to pass on frames
     ///  
     pub fn with_video(&mut self) -> TelloVideoReceiver  {
-        let (tx, rx) = make_tello_video_channel();
+        let (tx, rx) = make_tello_video_channel(self.video_channel_capacity);
         self.video_sender = Some(tx);
         rx
     }
 
+    /// Log state updates from the drone to a CSV file.
+    ///
+    /// *nb* As with `with_state()` this only works in AP mode
+    ///
+    /// The file is created (or overwritten) on connection, with a header 
+    /// row and then one row per update. Columns, in SI units except for 
+    /// angles:
+    /// - `time_s` Seconds since the Unix epoch
+    /// - `roll_deg`, `pitch_deg`, `yaw_deg` Attitude in degrees
+    /// - `height_m`, `barometer_m`, `time_of_flight_m` Heights in m
+    /// - `battery_percent` Battery level
+    /// - `motor_time_s` Time the motors have been active for
+    /// - `temperature_low_c`, `temperature_high_c` Temperatures in Celsius
+    /// - `velocity_x_m_s`, `velocity_y_m_s`, `velocity_z_m_s` Velocity in ms⁻¹
+    /// - `acceleration_x_m_s2`, `acceleration_y_m_s2`, `acceleration_z_m_s2` Acceleration in ms⁻²
+    ///
+    /// - `path` The file to write
+    ///
+    pub fn with_telemetry_csv<P: AsRef<Path>>(&mut self, path: P) {
+        self.telemetry_csv_path = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Record every command sent to the drone, with when it was sent, eg to
+    /// fly the same mission again with `Tello::replay_log()`.
+    ///
+    /// Returns the log, which is added to as commands are sent. Connecting 
+    /// again with the same options carries on the same log. WiFi passwords
+    /// are masked, eg `wifi mynet ****`.
+    ///
+    pub fn with_command_log(&mut self) -> CommandLog {
+        let log = CommandLog::default();
+        self.command_log = Some(log.clone());
+        log
+    }
+
+    /// Keep the drone inside a box, by refusing any move that would take it
+    /// outside with `TelloError::OutOfBounds` and hovering instead. 
+    ///
+    /// *nb* Remote control and `Tello::jump()` are not restricted, as where
+    /// they take the drone can't be predicted.
+    ///
+    /// - `bounds` The box to stay inside
+    ///
+    pub fn with_bounds(&mut self, bounds: FlightBounds) {
+        self.bounds = Some(bounds);
+    }
+
+    /// Automatically recover when the link to the drone is lost, eg a brief
+    /// WiFi dropout on a long range flight, by putting the drone back in 
+    /// command mode and restarting the state and video listeners. The 
+    /// channels already set up keep working.
+    ///
+    /// *nb* Only happens while `Tello::supervise()` is running, and 
+    /// requires state updates to notice the link has been lost.
+    ///
+    /// - `policy` When to give up the link as lost, and how to retry
+    ///
+    pub fn with_auto_reconnect(&mut self, policy: ReconnectPolicy) {
+        self.auto_reconnect = Some(policy);
+    }
+
     /// Returns the sender end of a channel for issuing commands to the
     /// drone, eg for a remote control application.
     ///
     pub fn with_command(&mut self) -> TelloCommandSender {
         let (tx, rx) = make_tello_command_channel();
-        self.command_receiver = Some(rx);
+        self.command_receiver = Some(Arc::new(Mutex::new(rx)));
         tx
     }
-}
\ No newline at end of file
+
+    /// As `with_command`, but also returns the receiver end of a channel 
+    /// the outcome of each command is passed back on, eg so a GUI can tell 
+    /// whether taking off succeeded.
+    ///
+    /// *nb* With results, `handle_commands` carries on after a command fails
+    /// rather than stopping with the error.
+    ///
+    pub fn with_command_results(&mut self) -> (TelloCommandSender, TelloCommandResultReceiver) {
+        let tx = self.with_command();
+        let (result_tx, result_rx) = make_tello_command_result_channel();
+        self.command_result_sender = Some(result_tx);
+        (tx, result_rx)
+    }
+
+    /// Returns the receiver end of a channel for changes in the WiFi 
+    /// connection, and watches that the host stays on the drone's network 
+    /// while connected. If it leaves, commands fail with 
+    /// `TelloError::WiFiLost` rather than an IO error or timeout, until it 
+    /// rejoins.
+    ///
+    /// *nb* Only works if the network is known, ie the drone was found with
+    /// `Tello::wait_for_wifi()` or similar.
+    ///
+    pub fn with_wifi_monitor(&mut self) -> WiFiEventReceiver {
+        let (tx, rx) = make_wifi_event_channel();
+        self.wifi_event_sender = Some(tx);
+        rx
+    }
+
+    /// As `with_command`, but also returns a handle which can be used to 
+    /// stop `handle_commands` and to wait until it has finished.
+    ///
+    pub fn with_command_handle(&mut self) -> (TelloCommandSender, TelloCommandHandle) {
+        let tx = self.with_command();
+        let (handle, control) = make_tello_command_handle();
+        self.command_control = Some(Arc::new(control));
+        (tx, handle)
+    }
+}
+
+/// Builds `TelloOptions`, see `TelloOptions::builder()`.
+#[derive(Debug, Default)]
+pub struct TelloOptionsBuilder {
+    options: TelloOptions,
+    handles: TelloHandles
+}
+
+/// The ends of any channels requested with `TelloOptionsBuilder`, for 
+/// communicating with the drone once connected.
+#[derive(Debug, Default)]
+pub struct TelloHandles {
+    /// State updates, if requested with `TelloOptionsBuilder::with_state()`.
+    pub state_receiver: Option<TelloStateReceiver>,
+
+    /// Video frames, if requested with `TelloOptionsBuilder::with_video()`.
+    pub video_receiver: Option<TelloVideoReceiver>,
+
+    /// For sending commands, if requested with `TelloOptionsBuilder::with_command()`,
+    /// `with_command_results()` or `with_command_handle()`.
+    pub command_sender: Option<TelloCommandSender>,
+
+    /// The outcome of each command, if requested with 
+    /// `TelloOptionsBuilder::with_command_results()`.
+    pub command_result_receiver: Option<TelloCommandResultReceiver>,
+
+    /// For stopping command handling, if requested with 
+    /// `TelloOptionsBuilder::with_command_handle()`.
+    pub command_handle: Option<TelloCommandHandle>
+}
+
+impl TelloOptionsBuilder {
+    /// See `TelloOptions::drone_host`
+    pub fn drone_host(mut self, host: &str) -> Self {
+        self.options.drone_host = host.to_string();
+        self
+    }
+
+    /// See `TelloOptions::control_port`
+    pub fn control_port(mut self, port: u16) -> Self {
+        self.options.control_port = port;
+        self
+    }
+
+    /// See `TelloOptions::state_port`
+    pub fn state_port(mut self, port: u16) -> Self {
+        self.options.state_port = port;
+        self
+    }
+
+    /// See `TelloOptions::video_port`
+    pub fn video_port(mut self, port: u16) -> Self {
+        self.options.video_port = port;
+        self
+    }
+
+    /// See `TelloOptions::command_timeout`
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.options.command_timeout = timeout;
+        self
+    }
+
+    /// See `TelloOptions::keep_alive`
+    pub fn keep_alive(mut self, keep_alive: bool) -> Self {
+        self.options.keep_alive = keep_alive;
+        self
+    }
+
+    /// See `TelloOptions::video_channel_capacity`
+    ///
+    /// *nb* Must be set before calling `with_video()`
+    pub fn video_channel_capacity(mut self, capacity: usize) -> Self {
+        self.options.video_channel_capacity = Some(capacity);
+        self
+    }
+
+    /// See `TelloOptions::with_state()`
+    pub fn with_state(mut self) -> Self {
+        self.handles.state_receiver = Some(self.options.with_state());
+        self
+    }
+
+    /// See `TelloOptions::with_video()`
+    pub fn with_video(mut self) -> Self {
+        self.handles.video_receiver = Some(self.options.with_video());
+        self
+    }
+
+    /// See `TelloOptions::with_command()`
+    pub fn with_command(mut self) -> Self {
+        self.handles.command_sender = Some(self.options.with_command());
+        self
+    }
+
+    /// See `TelloOptions::with_command_results()`
+    pub fn with_command_results(mut self) -> Self {
+        let (sender, result_receiver) = self.options.with_command_results();
+        self.handles.command_sender = Some(sender);
+        self.handles.command_result_receiver = Some(result_receiver);
+        self
+    }
+
+    /// See `TelloOptions::with_command_handle()`
+    pub fn with_command_handle(mut self) -> Self {
+        let (sender, handle) = self.options.with_command_handle();
+        self.handles.command_sender = Some(sender);
+        self.handles.command_handle = Some(handle);
+        self
+    }
+
+    /// Finish building, returning the options and the ends of any channels 
+    /// requested.
+    pub fn build(self) -> (TelloOptions, TelloHandles) {
+        (self.options, self.handles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_returns_the_channels_requested() {
+        let (options, handles) = TelloOptions::builder()
+            .video_channel_capacity(4)
+            .with_video()
+            .with_command_results()
+            .build();
+
+        assert_eq!(options.video_channel_capacity, Some(4));
+        assert!(options.video_sender.is_some());
+        assert!(options.command_receiver.is_some() && options.command_result_sender.is_some());
+        assert!(handles.video_receiver.is_some());
+        assert!(handles.command_sender.is_some() && handles.command_result_receiver.is_some());
+        assert!(handles.command_handle.is_none());
+
+        let (options, handles) = TelloOptions::builder().with_command_handle().build();
+        assert!(options.command_control.is_some());
+        assert!(handles.command_sender.is_some() && handles.command_handle.is_some());
+        assert!(handles.command_result_receiver.is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn small_move_policy_round_trips_through_json() {
+        for policy in [SmallMovePolicy::Reject, SmallMovePolicy::ClampUp, SmallMovePolicy::Ignore] {
+            let json = serde_json::to_string(&policy).unwrap();
+            assert_eq!(serde_json::from_str::<SmallMovePolicy>(&json).unwrap(), policy);
+        }
+    }
+}
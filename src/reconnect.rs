@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+/// How to recover when the link to the drone is lost, see 
+/// `TelloOptions::with_auto_reconnect()` and `Tello::supervise()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReconnectPolicy {
+    /// How long without state updates before the link is considered lost.
+    pub link_timeout: Duration,
+
+    /// Give up, with `TelloError::ConnectionLost`, after this many failed 
+    /// attempts to reconnect, or `None` to keep trying forever.
+    pub max_attempts: Option<u32>,
+
+    /// Delay before the first attempt, doubled after each failure.
+    pub initial_backoff: Duration,
+
+    /// Longest delay between attempts.
+    pub max_backoff: Duration
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            link_timeout: Duration::from_secs(2),
+            max_attempts: Some(10),
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5)
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before the given attempt, counting from 1.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// Whether another attempt is allowed after the given number of failures.
+    pub(crate) fn allows_attempt(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt <= max)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_policy_round_trips_through_json() {
+        for policy in [
+            ReconnectPolicy::default(), 
+            ReconnectPolicy { max_attempts: None, initial_backoff: Duration::from_millis(10), ..ReconnectPolicy::default() }
+        ] {
+            let json = serde_json::to_string(&policy).unwrap();
+            assert_eq!(serde_json::from_str::<ReconnectPolicy>(&json).unwrap(), policy);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{BufWriter, LineWriter, Write};
+use std::path::Path;
+use log::info;
+
+use crate::errors::Result;
+use crate::state::TelloStateReceiver;
+use crate::video::TelloVideoReceiver;
+use crate::telemetry::{TelemetryCsvWriter, unix_time};
+
+/// Records video and state to files sharing the same timebase, until both 
+/// channels close.
+///
+/// Given the path `flight`, writes
+/// - `flight.h264` The raw h264 video stream, playable in VLC, ffmpeg etc
+/// - `flight.frames.csv` For each frame, the time received (seconds since 
+///   the Unix epoch), offset into the video file and size in bytes
+/// - `flight.telemetry.csv` The state updates, as for 
+///   `TelloOptions::with_telemetry_csv()`, timestamped the same way
+///
+/// - `video_receiver` From `TelloOptions::with_video()`
+/// - `state_receiver` From `TelloOptions::with_state()`
+/// - `path` The path for the files, without extension
+///
+pub async fn record_video_and_state<P: AsRef<Path>>(mut video_receiver: TelloVideoReceiver, mut state_receiver: TelloStateReceiver, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let mut video = BufWriter::new(File::create(path.with_extension("h264"))?);
+    let mut frames = LineWriter::new(File::create(path.with_extension("frames.csv"))?);
+    let mut telemetry = TelemetryCsvWriter::create(path.with_extension("telemetry.csv"))?;
+
+    writeln!(frames, "time_s,offset_bytes,size_bytes")?;
+
+    info!("[Recording] START {}", path.display());
+
+    let mut offset = 0;
+    let mut video_open = true;
+    let mut state_open = true;
+    while video_open || state_open {
+        tokio::select! {
+            frame = video_receiver.recv(), if video_open => match frame {
+                Some(frame) => {
+                    video.write_all(&frame.data)?;
+                    video.flush()?;
+                    writeln!(frames, "{:.3},{offset},{}", unix_time(), frame.data.len())?;
+                    offset += frame.data.len();
+                }
+                None => video_open = false
+            },
+            state = state_receiver.recv(), if state_open => match state {
+                Some(state) => telemetry.write_state(&state)?,
+                None => state_open = false
+            }
+        }
+    }
+
+    info!("[Recording] END");
+
+    Ok(())
+}
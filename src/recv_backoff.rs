@@ -0,0 +1,73 @@
+use tokio::time::{sleep, Duration};
+
+/// Delay after the first failure to receive.
+const INITIAL_DELAY:Duration = Duration::from_millis(10);
+
+/// Longest delay after failures in a row.
+const MAX_DELAY:Duration = Duration::from_secs(1);
+
+/// Slows a listener down while receiving keeps failing, eg with the network
+/// down, so it doesn't spin and flood the log with warnings.
+#[derive(Debug, Default)]
+pub(crate) struct RecvBackoff {
+    failures: u32
+}
+
+impl RecvBackoff {
+    /// Counts a failure, returning whether it's worth a warning, ie the 
+    /// 1st, 2nd, 4th, 8th... in a row.
+    pub(crate) fn failed(&mut self) -> bool {
+        self.failures = self.failures.saturating_add(1);
+        self.failures.is_power_of_two()
+    }
+
+    /// Receiving worked, so the next failure is the first again.
+    pub(crate) fn succeeded(&mut self) {
+        self.failures = 0;
+    }
+
+    /// How many times in a row receiving has failed.
+    pub(crate) fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Waits before receiving again, doubling the delay after each failure
+    /// in a row.
+    pub(crate) async fn wait(&self) {
+        sleep(self.delay()).await;
+    }
+
+    fn delay(&self) -> Duration {
+        let factor = 2u32.saturating_pow(self.failures.saturating_sub(1));
+        INITIAL_DELAY.saturating_mul(factor).min(MAX_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let mut backoff = RecvBackoff::default();
+        backoff.failed();
+        assert_eq!(backoff.delay(), INITIAL_DELAY);
+        backoff.failed();
+        assert_eq!(backoff.delay(), INITIAL_DELAY * 2);
+        for _ in 0..100 {
+            backoff.failed();
+        }
+        assert_eq!(backoff.delay(), MAX_DELAY);
+
+        backoff.succeeded();
+        backoff.failed();
+        assert_eq!(backoff.delay(), INITIAL_DELAY);
+    }
+
+    #[test]
+    fn warnings_get_rarer() {
+        let mut backoff = RecvBackoff::default();
+        let warned: Vec<u32> = (1..=20).filter(|_| backoff.failed()).collect();
+        assert_eq!(warned, [1, 2, 4, 8, 16]);
+    }
+}
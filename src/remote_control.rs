@@ -0,0 +1,84 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::{spawn, task};
+use tokio::net::UdpSocket;
+use tokio::time::{sleep, Duration, Instant};
+use log::{debug, info, warn};
+
+use crate::keep_alive::CommandLock;
+
+/// The most recently sent remote control values, `(left_right, 
+/// forwards_backwards, up_down, yaw)`.
+pub(crate) type RemoteControlValues = (i8, i8, i8, i8);
+
+/// Remote control state shared with the repeater.
+#[derive(Debug, Default)]
+pub(crate) struct RemoteControlState {
+    pub(crate) values: Mutex<RemoteControlValues>,
+
+    /// Whether the last command sent was remote control with any non-zero 
+    /// value, ie the drone should still be moving.
+    pub(crate) active: AtomicBool
+}
+
+/// Default interval between repeats, ie 20Hz.
+const DEFAULT_REPEAT_INTERVAL:Duration = Duration::from_millis(50);
+
+/// Keeps re-sending the latest remote control values while they're 
+/// non-zero, see `TelloOptions::repeat_remote_control`.
+#[derive(Debug)]
+pub(crate) struct RemoteControlRepeater {
+    task: task::JoinHandle<()>,
+    interval: Arc<Mutex<Duration>>
+}
+
+impl RemoteControlRepeater {
+    pub(crate) fn start(sock: Arc<UdpSocket>, command_lock: CommandLock, state: Arc<RemoteControlState>) -> Self {
+        info!("[Tello] START REMOTE CONTROL REPEAT");
+
+        let interval = Arc::new(Mutex::new(DEFAULT_REPEAT_INTERVAL));
+        let repeat_interval = interval.clone();
+        let task = spawn(async move {
+            loop {
+                let interval = *repeat_interval.lock().unwrap();
+                sleep(interval).await;
+
+                if !state.active.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                let mut last_command = command_lock.lock().await;
+
+                // something else may have been sent while waiting
+                if !state.active.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                let (left_right, forwards_backwards, up_down, yaw) = *state.values.lock().unwrap();
+                let command = format!("rc {left_right} {forwards_backwards} {up_down} {yaw}");
+                debug!("[Tello] SEND {command} (repeat)");
+                if let Err(err) = sock.send(command.as_bytes()).await {
+                    warn!("[Tello] failed to repeat remote control ({err})");
+                }
+                *last_command = Instant::now();
+            }
+        });
+
+        Self { task, interval }
+    }
+
+    pub(crate) fn set_interval(&self, interval: Duration) {
+        *self.interval.lock().unwrap() = interval;
+    }
+
+    /// Stops without waiting, eg when the drone is dropped.
+    pub(crate) fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub(crate) async fn stop(self) {
+        info!("[Tello] STOP REMOTE CONTROL REPEAT");
+        self.task.abort();
+        let _ = self.task.await;
+    }
+}
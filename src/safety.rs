@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use log::warn;
+
+use crate::tello::{Tello, Connected};
+
+/// Try to land the drone if anything panics, so a bug doesn't leave it 
+/// flying into the furniture. Falls back to an emergency stop if landing 
+/// fails.
+///
+/// Any existing panic hook is still called first.
+///
+/// *nb* This is best effort only - landing runs as a task on the current
+/// Tokio runtime, so won't happen if the runtime is shutting down (eg the 
+/// panic was in `main`), or if the process aborts on panic. 
+///
+/// Must be called from within a Tokio runtime.
+///
+/// - `drone` The drone to land
+///
+pub fn install_panic_safety(drone: Arc<Tello<Connected>>) {
+    let runtime = Handle::current();
+    let previous_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        previous_hook(info);
+
+        warn!("[Tello] PANIC - landing");
+        let drone = drone.clone();
+        runtime.spawn(async move {
+            if let Err(err) = drone.land().await {
+                warn!("[Tello] failed to land ({err}), emergency stop");
+                let _ = drone.emergency_stop_unchecked().await;
+            }
+        });
+    }));
+}
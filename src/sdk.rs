@@ -0,0 +1,114 @@
+use crate::errors::{Result, TelloError};
+
+/// A Tello SDK command, for sending with `Tello::send_command()` and 
+/// getting back a parsed `SdkResponse`.
+///
+/// *nb* `emergency` is not included, as the drone doesn't respond to it - 
+/// use `Tello::emergency_stop()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SdkCommand {
+    /// Put the drone in command mode
+    Command,
+    TakeOff,
+    Land,
+    /// Stop and hover
+    Stop,
+    StreamOn,
+    StreamOff,
+    /// Move up, in cm
+    Up(u16),
+    /// Move down, in cm
+    Down(u16),
+    /// Move left, in cm
+    Left(u16),
+    /// Move right, in cm
+    Right(u16),
+    /// Move forwards, in cm
+    Forward(u16),
+    /// Move back, in cm
+    Back(u16),
+    /// Turn clockwise, in degrees
+    Clockwise(u16),
+    /// Turn counter-clockwise, in degrees
+    CounterClockwise(u16),
+    /// Set the speed, in cm/s
+    SetSpeed(u8),
+    QuerySpeed,
+    QueryBattery,
+    QueryFlightTime,
+    QueryWifiSignalToNoiseRatio,
+    QuerySdkVersion,
+    QuerySerialNumber
+}
+
+/// The drone's response to an `SdkCommand`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdkResponse {
+    /// The command succeeded
+    Ok,
+    /// Speed in cm/s
+    Speed(f32),
+    /// Battery level as a percentage
+    Battery(u8),
+    /// Flight time in seconds
+    FlightTime(u16),
+    /// WiFi signal to noise ratio as a percentage
+    WifiSignalToNoiseRatio(u8),
+    SdkVersion(String),
+    SerialNumber(String)
+}
+
+impl SdkCommand {
+    /// The command as sent to the drone, eg `cw 90`.
+    pub fn to_sdk_string(&self) -> String {
+        match self {
+            SdkCommand::Command => "command".to_string(),
+            SdkCommand::TakeOff => "takeoff".to_string(),
+            SdkCommand::Land => "land".to_string(),
+            SdkCommand::Stop => "stop".to_string(),
+            SdkCommand::StreamOn => "streamon".to_string(),
+            SdkCommand::StreamOff => "streamoff".to_string(),
+            SdkCommand::Up(distance) => format!("up {distance}"),
+            SdkCommand::Down(distance) => format!("down {distance}"),
+            SdkCommand::Left(distance) => format!("left {distance}"),
+            SdkCommand::Right(distance) => format!("right {distance}"),
+            SdkCommand::Forward(distance) => format!("forward {distance}"),
+            SdkCommand::Back(distance) => format!("back {distance}"),
+            SdkCommand::Clockwise(degrees) => format!("cw {degrees}"),
+            SdkCommand::CounterClockwise(degrees) => format!("ccw {degrees}"),
+            SdkCommand::SetSpeed(speed) => format!("speed {speed}"),
+            SdkCommand::QuerySpeed => "speed?".to_string(),
+            SdkCommand::QueryBattery => "battery?".to_string(),
+            SdkCommand::QueryFlightTime => "time?".to_string(),
+            SdkCommand::QueryWifiSignalToNoiseRatio => "wifi?".to_string(),
+            SdkCommand::QuerySdkVersion => "sdk?".to_string(),
+            SdkCommand::QuerySerialNumber => "sn?".to_string()
+        }
+    }
+
+    /// Parses the drone's response to this command.
+    ///
+    /// ```
+    /// use tello_edu::{SdkCommand, SdkResponse};
+    ///
+    /// assert_eq!(SdkCommand::QueryBattery.parse_response("87").unwrap(), SdkResponse::Battery(87));
+    /// assert_eq!(SdkCommand::Clockwise(90).parse_response("ok").unwrap(), SdkResponse::Ok);
+    /// assert!(SdkCommand::QueryBattery.parse_response("error").is_err());
+    /// ```
+    pub fn parse_response(&self, response: &str) -> Result<SdkResponse> {
+        match self {
+            SdkCommand::QuerySpeed => Ok(SdkResponse::Speed(value_as(response)?)),
+            SdkCommand::QueryBattery => Ok(SdkResponse::Battery(value_as(response)?)),
+            SdkCommand::QueryFlightTime => Ok(SdkResponse::FlightTime(value_as(response.trim_end_matches('s'))?)),
+            SdkCommand::QueryWifiSignalToNoiseRatio => Ok(SdkResponse::WifiSignalToNoiseRatio(value_as(response)?)),
+            SdkCommand::QuerySdkVersion => Ok(SdkResponse::SdkVersion(response.to_string())),
+            SdkCommand::QuerySerialNumber => Ok(SdkResponse::SerialNumber(response.to_string())),
+            _ if response == "ok" => Ok(SdkResponse::Ok),
+            _ => Err(TelloError::from_not_ok_response(response.to_string()))
+        }
+    }
+}
+
+fn value_as<T: std::str::FromStr>(response: &str) -> Result<T> {
+    response.parse::<T>().map_err(|_| TelloError::ParseError { msg: format!("unexpected response: \"{response}\"") })
+}
@@ -0,0 +1,97 @@
+use image::RgbImage;
+use openh264::decoder::Decoder;
+use openh264::formats::YUVSource;
+use log::debug;
+
+use crate::errors::{Result, TelloError};
+use crate::tello::{Tello, Connected};
+use crate::video::TelloVideoReceiver;
+
+impl Tello<Connected> {
+    /// Takes a still picture from the video, eg for a photo booth.
+    ///
+    /// Starts the video if needed, then decodes frames until one can be 
+    /// shown in full - frames received before the next keyframe can't be, 
+    /// and are skipped.
+    ///
+    /// - `video_receiver` From `TelloOptions::with_video()`
+    ///
+    pub async fn snapshot(&self, video_receiver: &mut TelloVideoReceiver) -> Result<RgbImage> {
+        self.start_video().await?;
+
+        let mut decoder = Decoder::new().map_err(|err| TelloError::DecodeError { msg: err.to_string() })?;
+        while let Some(frame) = video_receiver.recv().await {
+            match decoder.decode(&frame.data) {
+                Ok(Some(yuv)) => return Ok(yuv_to_rgb(&yuv)),
+                Ok(None) => debug!("[Video] incomplete frame, waiting for the next"),
+                Err(err) => debug!("[Video] can't decode frame ({err}), waiting for the next")
+            }
+        }
+
+        Err(TelloError::Generic { msg: "video ended before a frame could be decoded".to_string() })
+    }
+}
+
+/// Converts a decoded 4:2:0 frame to RGB, using the BT.601 coefficients.
+/// The frame is whatever size the video resolution gave, see
+/// `Tello::set_video_resolution()`.
+fn yuv_to_rgb(yuv: &impl YUVSource) -> RgbImage {
+    let (width, height) = (yuv.width() as u32, yuv.height() as u32);
+    let (y_stride, u_stride, v_stride) = (yuv.y_stride() as usize, yuv.u_stride() as usize, yuv.v_stride() as usize);
+    let (y_plane, u_plane, v_plane) = (yuv.y(), yuv.u(), yuv.v());
+
+    RgbImage::from_fn(width, height, |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let luma = y_plane[y * y_stride + x] as f32;
+        let u = u_plane[(y / 2) * u_stride + x / 2] as f32 - 128.0;
+        let v = v_plane[(y / 2) * v_stride + x / 2] as f32 - 128.0;
+
+        let r = luma + 1.402 * v;
+        let g = luma - 0.344_136 * u - 0.714_136 * v;
+        let b = luma + 1.772 * u;
+        image::Rgb([r.clamp(0.0, 255.0) as u8, g.clamp(0.0, 255.0) as u8, b.clamp(0.0, 255.0) as u8])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame made up in memory, with padded rows as the decoder gives.
+    struct TestFrame {
+        width: i32,
+        height: i32,
+        y: Vec<u8>,
+        u: Vec<u8>,
+        v: Vec<u8>
+    }
+
+    impl YUVSource for TestFrame {
+        fn width(&self) -> i32 { self.width }
+        fn height(&self) -> i32 { self.height }
+        fn y(&self) -> &[u8] { &self.y }
+        fn u(&self) -> &[u8] { &self.u }
+        fn v(&self) -> &[u8] { &self.v }
+        fn y_stride(&self) -> i32 { self.width + 4 }
+        fn u_stride(&self) -> i32 { self.width / 2 + 2 }
+        fn v_stride(&self) -> i32 { self.width / 2 + 2 }
+    }
+
+    #[test]
+    fn frames_are_converted_at_their_own_size() {
+        // 4x2, left half grey, right half red-ish
+        let frame = TestFrame {
+            width: 4,
+            height: 2,
+            y: [[128, 128, 76, 76, 0, 0, 0, 0]; 2].concat(),
+            u: vec![128, 85, 0, 0],
+            v: vec![128, 255, 0, 0]
+        };
+
+        let image = yuv_to_rgb(&frame);
+        assert_eq!(image.dimensions(), (4, 2));
+        assert_eq!(image.get_pixel(0, 1), &image::Rgb([128, 128, 128]));
+        let image::Rgb([r, g, b]) = *image.get_pixel(3, 1);
+        assert!(r > 240 && g < 20 && b < 20, "expected red, got {r} {g} {b}");
+    }
+}
@@ -1,10 +1,38 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use tokio::{spawn, task};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio::net::UdpSocket;
+use log::{info, warn};
 
 use crate::errors::{Result, TelloError};
+use crate::telemetry::TelemetryCsvWriter;
+use crate::recv_backoff::RecvBackoff;
 
-const STATE_UDP_PORT:u32 = 8890;
+pub(crate) const DEFAULT_STATE_UDP_PORT:u16 = 8890;
+
+/// Temperature, Celsius, above which the drone throttles itself and may 
+/// refuse to flip or take off.
+const OVERHEATING_TEMPERATURE:i16 = 90;
+
+/// Temperature, Celsius, the drone must cool to before it's no longer 
+/// considered to be overheating.
+const COOLED_TEMPERATURE:i16 = 85;
+
+/// Minimum height above the launch point, in cm, for the drone to be 
+/// considered flying.
+const FLYING_MIN_HEIGHT:i16 = 10;
+
+/// Distances to the ground, in cm, over which the drone's vision 
+/// positioning works.
+const VISION_POSITIONING_RANGE:std::ops::RangeInclusive<u16> = 30..=600;
+
+/// Any velocity beyond this is beyond anything the drone can fly, so must be
+/// noise.
+const MAX_PLAUSIBLE_VELOCITY:i16 = 100;
 
 pub type TelloStateSender = mpsc::UnboundedSender<TelloState>;
 pub type TelloStateReceiver = mpsc::UnboundedReceiver<TelloState>;
@@ -13,8 +41,48 @@ pub fn make_tello_state_channel() -> (TelloStateSender, TelloStateReceiver) {
     mpsc::unbounded_channel()
 }
 
+/// Always holds the latest state received from the drone, `None` until the
+/// first arrives, see `TelloOptions::with_state_watch()`.
+pub type TelloStateWatch = watch::Receiver<Option<TelloState>>;
+pub(crate) type TelloStateWatchSender = Arc<watch::Sender<Option<TelloState>>>;
+
+pub(crate) fn make_tello_state_watch() -> (TelloStateWatchSender, TelloStateWatch) {
+    let (tx, rx) = watch::channel(None);
+    (Arc::new(tx), rx)
+}
+
+/// The most recent state received from the drone, if any.
+pub(crate) type LatestState = Arc<Mutex<Option<TelloState>>>;
+
+/// When the most recent state was received, if ever.
+pub(crate) type LastReceived = Arc<Mutex<Option<Instant>>>;
+
+/// Everywhere received state is passed on to.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StateSinks {
+    pub(crate) sender: Option<TelloStateSender>,
+    pub(crate) watch: Option<TelloStateWatchSender>,
+    pub(crate) telemetry: Option<Arc<Mutex<TelemetryCsvWriter>>>,
+    pub(crate) latest: LatestState,
+    pub(crate) received_at: LastReceived,
+    pub(crate) overheating: Arc<AtomicBool>
+}
+
 /// The live state of the drone.
-#[derive(Debug, Default)]
+///
+/// With the `serde` feature it can be serialized, eg to forward over a 
+/// websocket.
+///
+/// ```
+/// # use tello_edu::TelloState;
+/// # #[cfg(feature = "serde")] {
+/// let state = TelloState::from_message("pitch:0;roll:0;yaw:-3;vgx:0;vgy:0;vgz:1;h:50;bat:82;").unwrap();
+/// let json = serde_json::to_string(&state).unwrap();
+/// assert_eq!(serde_json::from_str::<TelloState>(&json).unwrap(), state);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TelloState {
     /// Roll angle in degrees.
     pub roll: i16,
@@ -28,7 +96,8 @@ pub struct TelloState {
     /// Height above launch point in cm
     pub height: i16,
 
-    /// Barometer measurement in cm (height?)
+    /// Barometer measurement in m, as `Tello::barometer()` - an uncalibrated
+    /// altitude, so only changes in it are meaningful.
     pub barometer: f32,
 
     /// Battery level as percentage.
@@ -50,14 +119,112 @@ pub struct TelloState {
     pub velocity: Vector3<i16>,
 
     /// Acceleration, cms⁻²
-    pub acceleration: Vector3<f32>
+    pub acceleration: Vector3<f32>,
+
+    /// Id of the detected mission pad, or -1 if none.
+    pub mission_pad_id: i16,
+
+    /// Position relative to the detected mission pad in cm, all -1 if none.
+    pub mission_pad_position: Vector3<i16>,
+
+    /// Attitude relative to the detected mission pad in degrees, 
+    /// `(pitch, roll, yaw)`, all -1 if none.
+    pub mission_pad_pitch_roll_yaw: (i16, i16, i16),
+
+    /// Any other values in the state message, by key, eg those added by 
+    /// newer firmware.
+    pub extra: HashMap<String, String>,
+
+    /// The state message as received, trimmed.
+    pub raw: String
 }
 
-#[derive(Debug, Default)]
+/// A 3D vector, eg the drone's velocity or acceleration.
+///
+/// ```
+/// use tello_edu::Vector3;
+///
+/// let v = Vector3::new(1, -2, 3);
+/// assert_eq!(v.map(|c| c * 2), Vector3::from((2, -4, 6)));
+///
+/// let (x, y, z) = v.into();
+/// assert_eq!((x, y, z), (1, -2, 3));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3<T> {
-    x: T,
-    y: T,
-    z: T
+    pub x: T,
+    pub y: T,
+    pub z: T
+}
+
+impl<T> Vector3<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Applies a function to each component.
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Vector3<U> {
+        Vector3 { x: f(self.x), y: f(self.y), z: f(self.z) }
+    }
+
+    /// The components as an array, `[x, y, z]`.
+    pub fn to_array(self) -> [T; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl<T> From<(T, T, T)> for Vector3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl<T> From<Vector3<T>> for (T, T, T) {
+    fn from(v: Vector3<T>) -> Self {
+        (v.x, v.y, v.z)
+    }
+}
+
+impl<T> From<[T; 3]> for Vector3<T> {
+    fn from([x, y, z]: [T; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl<T> From<Vector3<T>> for [T; 3] {
+    fn from(v: Vector3<T>) -> Self {
+        v.to_array()
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<Vector3<f32>> for glam::Vec3 {
+    fn from(v: Vector3<f32>) -> Self {
+        glam::Vec3::new(v.x, v.y, v.z)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::Vec3> for Vector3<f32> {
+    fn from(v: glam::Vec3) -> Self {
+        Self { x: v.x, y: v.y, z: v.z }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: nalgebra::Scalar> From<Vector3<T>> for nalgebra::Vector3<T> {
+    fn from(v: Vector3<T>) -> Self {
+        nalgebra::Vector3::new(v.x, v.y, v.z)
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl<T: nalgebra::Scalar> From<nalgebra::Vector3<T>> for Vector3<T> {
+    fn from(v: nalgebra::Vector3<T>) -> Self {
+        let [x, y, z]: [T; 3] = v.into();
+        Self { x, y, z }
+    }
 }
 
 impl TelloState {
@@ -66,8 +233,38 @@ impl TelloState {
     /// Example message:
     /// "mid:-1;x:-100;y:-100;z:-100;mpry:-1,-1,-1;pitch:0;roll:0;yaw:-3;vgx:0;vgy:0;vgz:1;templ:58;temph:60;tof:71;h:50;bat:82;baro:-57.14;time:14;agx:17.00;agy:-4.00;agz:-956.00;"
     ///
+    /// ```
+    /// use tello_edu::{TelloState, Vector3};
+    ///
+    /// let state = TelloState::from_message("mid:-1;x:-100;y:-100;z:-100;mpry:-1,-1,-1;pitch:0;roll:0;yaw:-3;vgx:0;vgy:0;vgz:1;templ:58;temph:60;tof:71;h:50;bat:82;baro:-57.14;time:14;agx:17.00;agy:-4.00;agz:-956.00;").unwrap();
+    /// assert_eq!(state.mission_pad_id, -1);
+    /// assert_eq!(state.mission_pad_position, Vector3::new(-100, -100, -100));
+    /// assert_eq!(state.mission_pad_pitch_roll_yaw, (-1, -1, -1));
+    /// ```
+    ///
+    /// Values the crate doesn't know about are kept, along with the 
+    /// original message.
+    ///
+    /// ```
+    /// # use tello_edu::TelloState;
+    /// let state = TelloState::from_message("bat:82;newkey:42;\r\n").unwrap();
+    /// assert_eq!(state.battery, 82);
+    /// assert_eq!(state.extra.get("newkey").map(String::as_str), Some("42"));
+    /// assert_eq!(state.raw, "bat:82;newkey:42;");
+    /// ```
+    ///
     pub fn from_message(s: &str) -> Result<TelloState> {
-        let mut state = TelloState::default();
+        let s = s.trim();
+        let mut state = TelloState {
+            raw: s.to_string(),
+            mission_pad_id: -1,
+            mission_pad_position: Vector3::new(-1, -1, -1),
+            mission_pad_pitch_roll_yaw: (-1, -1, -1),
+            ..Default::default()
+        };
+
+        let mut temperature_low = None;
+        let mut temperature_high = None;
 
         for f in s.split(";") {
             if f.is_empty() { continue; }
@@ -83,20 +280,96 @@ impl TelloState {
                 "bat" => state.battery = value_as(&v)?,
                 "tof" => state.time_of_flight = value_as(&v)?,
                 "time" => state.motor_time = value_as(&v)?,
-                "templ" => state.temperature_low = value_as(&v)?,
-                "temph" => state.temperature_high = value_as(&v)?,
+                "templ" => temperature_low = Some(value_as(&v)?),
+                "temph" => temperature_high = Some(value_as(&v)?),
                 "vgx" => state.velocity.x = value_as(&v)?,
                 "vgy" => state.velocity.y = value_as(&v)?,
                 "vgz" => state.velocity.z = value_as(&v)?,
                 "agx" => state.acceleration.x = value_as(&v)?,
                 "agy" => state.acceleration.y = value_as(&v)?,
                 "agz" => state.acceleration.z = value_as(&v)?,
-                _ => {}
+                "mid" => state.mission_pad_id = value_as(&v)?,
+                "x" => state.mission_pad_position.x = value_as(&v)?,
+                "y" => state.mission_pad_position.y = value_as(&v)?,
+                "z" => state.mission_pad_position.z = value_as(&v)?,
+                "mpry" => state.mission_pad_pitch_roll_yaw = triple_as(&v)?,
+                _ => { state.extra.insert(k, v); }
             }
         }
 
+        // if only one temperature arrived, take it as both
+        if let Some(t) = temperature_low.or(temperature_high) {
+            state.temperature_low = temperature_low.unwrap_or(t);
+            state.temperature_high = temperature_high.unwrap_or(t);
+        }
+
         Ok(state)
     }
+
+    /// The temperature in Celsius, midway between the lowest and highest.
+    ///
+    /// ```
+    /// # use tello_edu::TelloState;
+    /// let state = TelloState::from_message("mid:-1;x:-100;y:-100;z:-100;mpry:-1,-1,-1;pitch:0;roll:0;yaw:-3;vgx:0;vgy:0;vgz:1;templ:58;temph:60;tof:71;h:50;bat:82;baro:-57.14;time:14;agx:17.00;agy:-4.00;agz:-956.00;").unwrap();
+    /// assert_eq!(state.temperature(), 59.0);
+    ///
+    /// let state = TelloState::from_message("temph:60;").unwrap();
+    /// assert_eq!(state.temperature_low, 60);
+    /// assert_eq!(state.temperature(), 60.0);
+    /// ```
+    pub fn temperature(&self) -> f32 {
+        (self.temperature_low as f32 + self.temperature_high as f32) / 2.0
+    }
+
+    /// Whether the drone appears to be in the air.
+    ///
+    /// The state messages have no explicit flying/landed flag, so this is a
+    /// heuristic - the motors must have been running and the drone must be 
+    /// at least 10cm above its launch point.
+    ///
+    pub fn is_flying(&self) -> bool {
+        self.motor_time > 0 && self.height >= FLYING_MIN_HEIGHT
+    }
+
+    /// Whether the drone has any velocity.
+    ///
+    /// ```
+    /// # use tello_edu::TelloState;
+    /// let state = TelloState::from_message("vgx:5;vgy:0;vgz:0;agx:17.00;agy:-4.00;agz:-956.00;").unwrap();
+    /// assert_eq!(state.velocity.x, 5);
+    /// assert_eq!(state.acceleration.z, -956.0);
+    /// assert!(state.is_moving());
+    /// ```
+    pub fn is_moving(&self) -> bool {
+        self.velocity.x != 0 || self.velocity.y != 0 || self.velocity.z != 0
+    }
+
+    /// Whether the drone's vision positioning, which it uses to hold its 
+    /// position, looks to be working.
+    ///
+    /// This is a best effort guess from a single update - the downward 
+    /// sensor must see the ground within its range, and the velocities 
+    /// must be plausible, as they go wild when positioning is lost, eg in
+    /// low light or over a reflective floor.
+    ///
+    /// ```
+    /// # use tello_edu::TelloState;
+    /// let good = TelloState::from_message("vgx:3;vgy:-1;vgz:0;tof:95;h:80;").unwrap();
+    /// assert!(good.is_position_hold_reliable());
+    ///
+    /// let too_high = TelloState::from_message("vgx:3;vgy:-1;vgz:0;tof:6553;h:80;").unwrap();
+    /// assert!(!too_high.is_position_hold_reliable());
+    ///
+    /// let noisy = TelloState::from_message("vgx:-310;vgy:245;vgz:0;tof:95;h:80;").unwrap();
+    /// assert!(!noisy.is_position_hold_reliable());
+    /// ```
+    pub fn is_position_hold_reliable(&self) -> bool {
+        let plausible = |v: i16| v.abs() <= MAX_PLAUSIBLE_VELOCITY;
+        VISION_POSITIONING_RANGE.contains(&self.time_of_flight)
+            && plausible(self.velocity.x) 
+            && plausible(self.velocity.y) 
+            && plausible(self.velocity.z)
+    }
 }
 
 fn split_key_value(kv: &str) -> Result<(String, String)> {
@@ -110,46 +383,153 @@ fn value_as<T: std::str::FromStr>(s: &str) -> Result<T> {
     s.parse::<T>().map_err(|_| TelloError::ParseError { msg: s.to_string() })
 }
 
+/// Parses a comma separated triple, eg "-1,-1,-1"
+fn triple_as<T: std::str::FromStr>(s: &str) -> Result<(T, T, T)> {
+    let mut i = s.split(",");
+    let mut next = || value_as(i.next().ok_or_else(|| TelloError::ParseError { msg: s.to_string() })?);
+    Ok((next()?, next()?, next()?))
+}
+
 // fn value_as_some<T: std::str::FromStr>(s: &str) -> Result<Option<T>> {
 //     let v = s.parse::<T>().map_err(|_| TelloError::ParseError { msg: s.to_string() })?;
 //     Ok(Some(v))
 // }
 
+/// Warns when the drone starts or stops overheating.
+fn monitor_temperature(state: &TelloState, overheating: &AtomicBool) {
+    let temperature = state.temperature_high;
+    let was_overheating = overheating.load(Ordering::Relaxed);
+
+    if !was_overheating && temperature >= OVERHEATING_TEMPERATURE {
+        warn!("[State] WARNING drone is overheating ({temperature}°C), performance will be reduced and flips may fail");
+        overheating.store(true, Ordering::Relaxed);
+    }
+    else if was_overheating && temperature <= COOLED_TEMPERATURE {
+        info!("[State] drone has cooled down ({temperature}°C)");
+        overheating.store(false, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct StateListener {
-    task: task::JoinHandle<()>
+    task: task::JoinHandle<()>,
+    pub(crate) local_address: SocketAddr,
+    pub(crate) sinks: StateSinks
 }   
 
 impl StateListener {
-    pub(crate) async fn start_listening(sender:TelloStateSender) -> Result<Self> { 
-        let local_address = format!("0.0.0.0:{STATE_UDP_PORT}");
-        println!("[State] START LISTENING at {local_address}");
+    pub(crate) async fn start_listening(local_address:SocketAddr, sinks:StateSinks) -> Result<Self> { 
+        info!("[State] START LISTENING at {local_address}");
 
         let sock = UdpSocket::bind(&local_address).await?;
 
+        let StateSinks { mut sender, watch, mut telemetry, latest, received_at, overheating } = sinks.clone();
+
         let task = spawn(async move {
+            let mut backoff = RecvBackoff::default();
             loop {
                 let s = &sock;
                 let mut buf = vec![0; 1024];        
-                let n = s.recv(&mut buf).await.unwrap();
+                let n = match s.recv(&mut buf).await {
+                    Ok(n) => n,
+                    Err(err) => {
+                        if backoff.failed() {
+                            warn!("[State] failed to receive ({err}), {} time(s) in a row", backoff.failures());
+                        }
+                        backoff.wait().await;
+                        continue;
+                    }
+                };
+                backoff.succeeded();
 
                 buf.truncate(n);
-                let r = String::from_utf8(buf).unwrap();
+                let r = match String::from_utf8(buf) {
+                    Ok(r) => r,
+                    Err(err) => {
+                        warn!("[State] failed to decode state ({err}), ignored");
+                        continue;
+                    }
+                };
                 let raw_state = r.trim().to_string();
 
-                let state = TelloState::from_message(&raw_state).unwrap();
-                sender.send(state).unwrap();
+                let state = match TelloState::from_message(&raw_state) {
+                    Ok(state) => state,
+                    Err(err) => {
+                        warn!("[State] failed to parse \"{raw_state}\" ({err}), ignored");
+                        continue;
+                    }
+                };
+
+                *latest.lock().unwrap() = Some(state.clone());
+                *received_at.lock().unwrap() = Some(Instant::now());
+
+                monitor_temperature(&state, &overheating);
+
+                if let Some(writer) = &telemetry {
+                    let written = writer.lock().unwrap().write_state(&state);
+                    if let Err(err) = written {
+                        warn!("[State] failed to write telemetry ({err}), stopped writing");
+                        telemetry = None;
+                    }
+                }
+
+                if let Some(tx) = &watch {
+                    // no receivers is fine, as more can subscribe later
+                    tx.send_replace(Some(state.clone()));
+                }
+
+                if let Some(tx) = &sender {
+                    if tx.send(state).is_err() {
+                        info!("[State] receiver dropped, no longer passing on state");
+                        sender = None;
+                    }
+                }
             }
         });
 
-        Ok(Self { task })
+        Ok(Self { task, local_address, sinks })
     }
 
-    pub(crate) async fn stop_listening(&self) -> Result<()> {
-        println!("[State] STOP LISTENING");
+    /// Stops without waiting, eg when the drone is dropped. The socket is
+    /// released once the runtime next polls the aborted task.
+    pub(crate) fn abort(&self) {
         self.task.abort();
-        // TODO?
-        // let _err = self.task.await;
-        Ok(())
     }
- }
\ No newline at end of file
+
+    /// Stops listening, only returning once the socket has been released.
+    pub(crate) async fn stop_listening(self) -> Result<()> {
+        info!("[State] STOP LISTENING");
+        self.task.abort();
+        match self.task.await {
+            Err(err) if err.is_panic() => Err(TelloError::Generic { msg: format!("state listener failed - {err}") }),
+            _ => Ok(())
+        }
+    }
+ }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips_through_json() {
+        let messages = [
+            "mid:-1;x:-100;y:-100;z:-100;mpry:-1,-1,-1;pitch:0;roll:0;yaw:-3;vgx:0;vgy:0;vgz:1;templ:58;temph:60;tof:71;h:50;bat:82;baro:-57.14;time:14;agx:17.00;agy:-4.00;agz:-956.00;",
+            "mid:3;x:20;y:-35;z:80;mpry:1,-2,90;pitch:2;roll:-1;yaw:179;vgx:-10;vgy:5;vgz:0;templ:85;temph:91;tof:10;h:120;bat:15;baro:12.50;time:300;agx:-1.50;agy:2.25;agz:-1001.00;newkey:42;",
+            "bat:82;"
+        ];
+        for message in messages {
+            let state = TelloState::from_message(message).unwrap();
+            let json = serde_json::to_string(&state).unwrap();
+            assert_eq!(serde_json::from_str::<TelloState>(&json).unwrap(), state);
+        }
+    }
+
+    #[test]
+    fn vector_round_trips_through_json() {
+        let v = Vector3::new(1.5f32, -2.0, 1000.25);
+        let json = serde_json::to_string(&v).unwrap();
+        assert_eq!(json, r#"{"x":1.5,"y":-2.0,"z":1000.25}"#);
+        assert_eq!(serde_json::from_str::<Vector3<f32>>(&json).unwrap(), v);
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::Result;
+use crate::state::TelloState;
+
+const CSV_HEADER:&str = "time_s,roll_deg,pitch_deg,yaw_deg,height_m,barometer_m,time_of_flight_m,battery_percent,motor_time_s,temperature_low_c,temperature_high_c,velocity_x_m_s,velocity_y_m_s,velocity_z_m_s,acceleration_x_m_s2,acceleration_y_m_s2,acceleration_z_m_s2";
+
+/// A snapshot of the values that can be cheaply queried from the drone, see
+/// `Tello::poll_telemetry()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Telemetry {
+    /// Speed in cm/s.
+    pub speed: f32,
+
+    /// Flight time in seconds.
+    pub flight_time: u16,
+
+    /// Battery level as percentage.
+    pub battery: u8
+}
+
+/// Writes the drone state to a CSV file, one row per update.
+#[derive(Debug)]
+pub(crate) struct TelemetryCsvWriter {
+    file: LineWriter<File>
+}
+
+impl TelemetryCsvWriter {
+    /// Creates (or truncates) the file and writes the header row.
+    pub(crate) fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut file = LineWriter::new(File::create(path)?);
+        writeln!(file, "{CSV_HEADER}")?;
+        Ok(Self { file })
+    }
+
+    /// Writes a row for the state, timestamped with the current time.
+    pub(crate) fn write_state(&mut self, state: &TelloState) -> Result<()> {
+        let time = unix_time();
+
+        // cm → m, the barometer is already in m
+        let m = |cm: f32| cm / 100.0;
+
+        writeln!(self.file, "{time:.3},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            state.roll,
+            state.pitch,
+            state.yaw,
+            m(state.height as f32),
+            state.barometer,
+            m(state.time_of_flight as f32),
+            state.battery,
+            state.motor_time,
+            state.temperature_low,
+            state.temperature_high,
+            m(state.velocity.x as f32),
+            m(state.velocity.y as f32),
+            m(state.velocity.z as f32),
+            m(state.acceleration.x),
+            m(state.acceleration.y),
+            m(state.acceleration.z)
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The current time in seconds since the Unix epoch.
+pub(crate) fn unix_time() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_is_written_in_metres() {
+        let path = std::env::temp_dir().join(format!("tello-telemetry-{}.csv", std::process::id()));
+        let state = TelloState::from_message("mid:-1;x:-100;y:-100;z:-100;mpry:-1,-1,-1;pitch:0;roll:0;yaw:-3;vgx:0;vgy:0;vgz:1;templ:58;temph:60;tof:71;h:50;bat:82;baro:-57.14;time:14;agx:17.00;agy:-4.00;agz:-956.00;").unwrap();
+
+        let mut writer = TelemetryCsvWriter::create(&path).unwrap();
+        writer.write_state(&state).unwrap();
+        drop(writer);
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+        assert_eq!(row.len(), CSV_HEADER.split(',').count());
+        // height, barometer, time of flight
+        assert_eq!(row[4..7], ["0.5", "-57.14", "0.71"]);
+        assert_eq!(row[7], "82");
+        assert!(lines.next().is_none());
+    }
+}
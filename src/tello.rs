@@ -1,17 +1,123 @@
+use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::net::UdpSocket;
 use tokio::time::{sleep, Duration};
 use tokio::sync::Mutex;
+use log::{debug, info, warn};
 
 use crate::errors::{Result, TelloError};
-use crate::wifi::wait_for_wifi;
+use crate::wifi::{wait_for_wifi, WiFiMonitor};
+use crate::events::TelloEvent;
 use crate::state::*;
 use crate::video::*;
 use crate::command::*;
-use crate::options::TelloOptions;
+use crate::options::{TelloOptions, SmallMovePolicy};
+use crate::bounds::PositionEstimate;
+use crate::capabilities::{Capability, parse_sdk_version};
+use crate::reconnect::ReconnectPolicy;
+use crate::direction::Direction;
+use crate::mission_pads::MissionPadDirection;
+use crate::keep_alive::{KeepAlive, CommandLock};
+use crate::sdk::{SdkCommand, SdkResponse};
+use crate::remote_control::{RemoteControlState, RemoteControlRepeater};
+use crate::telemetry::{Telemetry, TelemetryCsvWriter};
 
-const DEFAULT_DRONE_HOST:&str = "192.168.10.1";
+/// Largest response datagram, ie the most the drone sends in one packet.
+const MAX_RESPONSE_CHUNK_SIZE:usize = 1460;
 
-const CONTROL_UDP_PORT:i32 = 8889;
+/// How long to wait for the rest of a response split across datagrams.
+const RESPONSE_CONTINUATION_TIMEOUT:Duration = Duration::from_millis(50);
+
+/// Responses the drone sends whole without ending them with a newline, so
+/// there's no need to wait for any more.
+const UNTERMINATED_RESPONSES:&[&str] = &["ok", "forced stop"];
+
+/// How many times to try binding the local socket for commands.
+const BIND_ATTEMPTS:u32 = 10;
+
+/// The shortest move the drone accepts, in cm.
+const MIN_MOVE_DISTANCE:u16 = 20;
+
+/// Interval between remote control updates when sending continuously.
+const REMOTE_CONTROL_INTERVAL:Duration = Duration::from_millis(50);
+
+/// How long to wait for each query when probing capabilities.
+const PROBE_TIMEOUT:Duration = Duration::from_secs(1);
+
+/// How long to wait for each query when gathering diagnostics.
+const DIAGNOSTICS_QUERY_TIMEOUT:Duration = Duration::from_secs(3);
+
+/// How often the supervisor checks the link to the drone.
+const SUPERVISOR_INTERVAL:Duration = Duration::from_millis(250);
+
+/// Furthest the drone can be told to go in each direction, in cm.
+const MAX_COORDINATE:i16 = 500;
+
+/// Distances accepted by the move commands, in cm.
+const MOVE_DISTANCE_RANGE:std::ops::RangeInclusive<u16> = MIN_MOVE_DISTANCE..=500;
+
+/// Angles accepted by the turn commands, in degrees.
+const TURN_DEGREES_RANGE:std::ops::RangeInclusive<u16> = 1..=360;
+
+/// Speeds accepted by `speed`, `go` and `jump`, in cm/s.
+const SPEED_RANGE:std::ops::RangeInclusive<u8> = 10..=100;
+
+/// Speeds accepted by `curve`, in cm/s.
+const CURVE_SPEED_RANGE:std::ops::RangeInclusive<u8> = 10..=60;
+
+/// Breathing frequencies the expansion board LED accepts, in Hz.
+const LED_FREQUENCY_RANGE:std::ops::RangeInclusive<f32> = 0.1..=2.5;
+
+/// How fast text scrolls across the expansion board LED matrix, in Hz.
+const MATRIX_SCROLL_FREQUENCY:f32 = 1.0;
+
+/// The longest text the expansion board LED matrix can show.
+const MAX_MATRIX_TEXT_LENGTH:usize = 70;
+
+/// How many turns `turn_to_heading` makes to reach the heading at most.
+const TURN_TO_HEADING_ATTEMPTS:u32 = 3;
+
+/// How close to the heading, in degrees, is close enough for 
+/// `turn_to_heading`.
+const TURN_TO_HEADING_TOLERANCE:i16 = 2;
+
+/// How many state updates in a row must show no movement for `fly_to` to 
+/// count the drone as stopped.
+const FLY_TO_STEADY_UPDATES:u32 = 5;
+
+/// Velocity, in any direction, slow enough for `fly_to` to count as 
+/// stopped, allowing for a little noise.
+const FLY_TO_STOPPED_VELOCITY:i16 = 1;
+
+/// How much longer than the move should take `fly_to` waits for the drone to
+/// stop.
+const FLY_TO_SETTLE_MARGIN:Duration = Duration::from_secs(5);
+
+/// The numbers printed on the mission pads.
+const MISSION_PAD_IDS:std::ops::RangeInclusive<u8> = 1..=8;
+
+/// How long `emergency_stop` works for after being armed.
+const EMERGENCY_STOP_ARMED_TIME:Duration = Duration::from_secs(3);
+
+/// Height in cm the drone usually hovers at after taking off.
+const TAKE_OFF_HEIGHT:f32 = 80.0;
+
+/// Change in height, in cm, still considered to be hovering steadily.
+const HOVER_HEIGHT_TOLERANCE:i16 = 2;
+
+/// How often `take_off_reporting` checks the height while waiting for the 
+/// drone to settle.
+const TAKE_OFF_POLL_INTERVAL:Duration = Duration::from_millis(100);
+
+/// How many checks in a row must find the height steady for 
+/// `take_off_reporting` to count the drone as hovering.
+const TAKE_OFF_STEADY_CHECKS:u32 = 5;
+
+/// How long `take_off_reporting` waits for the height to settle before 
+/// giving up and returning it anyway.
+const TAKE_OFF_SETTLE_TIMEOUT:Duration = Duration::from_secs(5);
 
 /// Initial state - no WiFi network
 #[derive(Debug)]
@@ -19,15 +125,80 @@ pub struct NoWifi;
 
 /// The drone WiFi has been joined, but no UDP messages have been sent or received.
 #[derive(Debug)]
-pub struct Disconnected;
+pub struct Disconnected {
+    ssid: Option<String>
+}
 
 /// The connection exchange has been completed and the drone is ready to fly.
 #[derive(Debug)]
 pub struct Connected {
-    sock: UdpSocket,
-    state_listener: Option<StateListener>,
-    video_listener: Option<VideoListener>,
-    command_receiver: Option<Mutex<TelloCommandReceiver>>
+    ssid: Option<String>,
+    sock: std::sync::Mutex<Option<Arc<UdpSocket>>>,
+    command_lock: CommandLock,
+    keep_alive: Mutex<Option<KeepAlive>>,
+    state_listener: Mutex<Option<StateListener>>,
+    video_listener: Mutex<Option<VideoListener>>,
+    options: TelloOptions,
+    latest_state: LatestState,
+    video_stats: SharedVideoStats,
+    overheating: Arc<AtomicBool>,
+    remote_control: Arc<RemoteControlState>,
+    remote_control_repeater: Mutex<Option<RemoteControlRepeater>>,
+    position: std::sync::Mutex<PositionEstimate>,
+    capabilities: Option<HashSet<Capability>>,
+    airborne_on_connect: bool,
+    command_log_started: std::time::Instant,
+    flight_status: std::sync::Mutex<FlightStatus>,
+    emergency_stop_armed_at: std::sync::Mutex<Option<std::time::Instant>>,
+    ports: std::sync::Mutex<(u16, u16)>,
+    wifi_monitor: Mutex<Option<WiFiMonitor>>,
+    wifi_lost: Arc<AtomicBool>
+}
+
+impl Drop for Connected {
+    fn drop(&mut self) {
+        // nothing else should send anything now
+        if let Some(repeater) = self.remote_control_repeater.get_mut() {
+            repeater.abort();
+        }
+        if let Some(keep_alive) = self.keep_alive.get_mut() {
+            keep_alive.abort();
+        }
+
+        // release the local ports and stop polling the WiFi
+        if let Some(state_listener) = self.state_listener.get_mut() {
+            state_listener.abort();
+        }
+        if let Some(video_listener) = self.video_listener.get_mut() {
+            video_listener.abort();
+        }
+        if let Some(wifi_monitor) = self.wifi_monitor.get_mut() {
+            wifi_monitor.abort();
+        }
+
+        // a panic elsewhere shouldn't stop the drone being stopped
+        let landed = *self.flight_status.get_mut().unwrap_or_else(|e| e.into_inner()) == FlightStatus::Landed;
+        if self.options.stop_on_drop && !landed {
+            warn!("[Tello] dropped while flying, stopping");
+            let sock = self.sock.get_mut().unwrap_or_else(|e| e.into_inner());
+            match sock.as_ref().map(|sock| sock.try_send(b"rc 0 0 0 0")) {
+                Some(Ok(_)) => {}
+                Some(Err(err)) => warn!("[Tello] failed to stop ({err})"),
+                None => warn!("[Tello] failed to stop (no socket for commands)")
+            }
+        }
+    }
+}
+
+/// Whether the drone is on the ground or in the air, as far as the commands 
+/// sent to it go.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum FlightStatus {
+    #[default]
+    Landed,
+    TakingOff,
+    Flying,
+    Landing
 }
 
 /// For interacting with the Tello EDU drone using the simple text-based UDP protocol.
@@ -77,24 +248,62 @@ impl Tello<NoWifi> {
         Self { inner: NoWifi }
     }
 
-    /// Wait until the host joins the drone's WiFi network
+    /// Wait until the host joins the drone's WiFi network, ie one with a 
+    /// name starting with "TELLO"
     ///
     /// *nb* exactly how the the network is joined is up to you
     ///
     pub async fn wait_for_wifi(&self) -> Result<Tello<Disconnected>>  {
-        println!("[Tello] waiting for WiFi...");
-        wait_for_wifi("TELLO").await?;
-        Ok(Tello { inner: Disconnected })
+        info!("[Tello] waiting for WiFi...");
+        let ssid = wait_for_wifi(|ssid| ssid.starts_with("TELLO"), None).await?;
+        Ok(Tello::joined_wifi(ssid))
+    }
+
+    /// As `wait_for_wifi()`, but giving up with `TelloError::Timeout` if 
+    /// the network hasn't been joined in time, eg because the drone is 
+    /// off.
+    ///
+    /// - `timeout` How long to wait
+    ///
+    pub async fn wait_for_wifi_timeout(&self, timeout: Duration) -> Result<Tello<Disconnected>>  {
+        info!("[Tello] waiting up to {timeout:#?} for WiFi...");
+        let ssid = wait_for_wifi(|ssid| ssid.starts_with("TELLO"), Some(timeout)).await?;
+        Ok(Tello::joined_wifi(ssid))
+    }
+
+    /// Wait until the host joins the WiFi network with exactly the given 
+    /// name, eg to use a particular drone when several are in range.
+    ///
+    /// *nb* exactly how the the network is joined is up to you
+    ///
+    /// - `ssid` The name of the drone's network
+    ///
+    pub async fn wait_for_wifi_exact(&self, ssid: &str) -> Result<Tello<Disconnected>>  {
+        info!("[Tello] waiting for WiFi {ssid}...");
+        let ssid = wait_for_wifi(|s| s == ssid, None).await?;
+        Ok(Tello::joined_wifi(ssid))
     }
 
     /// Use this if you are already in the appropriate WiFi network. 
     pub async fn assume_wifi(&self) -> Result<Tello<Disconnected>>  {
-        println!("[Tello] assuming WiFi has already been joined");
-        Ok(Tello { inner: Disconnected })
+        info!("[Tello] assuming WiFi has already been joined");
+        Ok(Tello { inner: Disconnected { ssid: None } })
     }    
 }
 
 impl Tello<Disconnected> {
+    fn joined_wifi(ssid: Option<String>) -> Self {
+        if let Some(ssid) = &ssid {
+            info!("[Tello] joined WiFi {ssid}");
+        }
+        Tello { inner: Disconnected { ssid } }
+    }
+
+    /// The name of the WiFi network joined, if known.
+    pub fn ssid(&self) -> Option<&str> {
+        self.inner.ssid.as_deref()
+    }
+
     /// Connect to the drone using the default options, ie
     /// - using the drone's own WiFi
     /// - drone address 192.168.10.1
@@ -109,85 +318,340 @@ impl Tello<Disconnected> {
     /// - `options` Connection options
     ///
     pub async fn connect_with(&self, options:TelloOptions) -> Result<Tello<Connected>> {
-        let local_address = format!("0.0.0.0:{CONTROL_UDP_PORT}");
-
-        let drone_host = DEFAULT_DRONE_HOST;
-        let drone_address = format!("{drone_host}:{CONTROL_UDP_PORT}");
-
-        println!("[Tello] CONNECT {local_address} → {drone_address}");
-
-        // bind local socket
-        println!("[Tello] binding local {local_address}...");
-        let sock = UdpSocket::bind(&local_address).await?;
-        
-        // connect to drone
-        println!("[Tello] connecting to drone at {drone_address}...");
-        let mut i = 0;
-        loop {
-            i = i + 1;
-            match sock.connect(&drone_address).await {
-                Ok(_) => {
-                    println!("[Tello] CONNECTED");
-                    break;
-                }
-                Err(err) => {
-                    println!("[Tello] connection attempt #{i} failed ({err}), retrying...");
-                    sleep(Duration::from_millis(100)).await;
-                }
-            }
-        }
+        let sock = open_control_socket(&options).await?;
 
         // connected drone, control only
-        let mut drone = Tello { inner: Connected { sock, state_listener: None, video_listener: None, command_receiver: None } };
+        let mut drone = Tello { 
+            inner: Connected { 
+                ssid: self.inner.ssid.clone(),
+                sock: std::sync::Mutex::new(Some(Arc::new(sock))), 
+                command_lock: Arc::new(Mutex::new(tokio::time::Instant::now())),
+                keep_alive: Mutex::new(None),
+                state_listener: Mutex::new(None), 
+                video_listener: Mutex::new(None), 
+                options: options.clone(), 
+                latest_state: LatestState::default(), 
+                video_stats: SharedVideoStats::default(), 
+                overheating: Arc::default(), 
+                remote_control: Arc::default(), 
+                remote_control_repeater: Mutex::new(None), 
+                position: Default::default(), 
+                capabilities: None, 
+                airborne_on_connect: false,
+                command_log_started: command_log_started(&options),
+                flight_status: Default::default(),
+                emergency_stop_armed_at: Default::default(),
+                ports: std::sync::Mutex::new((options.state_port, options.video_port)),
+                wifi_monitor: Mutex::new(None),
+                wifi_lost: Arc::default()
+            } 
+        };
 
         // want drone state?
-        if let Some(state_tx) = &options.state_sender {
-            let state_listener = StateListener::start_listening(state_tx.clone()).await?;
-            drone.inner.state_listener = Some(state_listener);
+        let telemetry = match &options.telemetry_csv_path {
+            Some(path) => Some(Arc::new(std::sync::Mutex::new(TelemetryCsvWriter::create(path)?))),
+            None => None
+        };
+        if options.state_sender.is_some() || options.state_watch_sender.is_some() || telemetry.is_some() {
+            let sinks = StateSinks { sender: options.state_sender.clone(), watch: options.state_watch_sender.clone(), telemetry, latest: drone.inner.latest_state.clone(), received_at: Default::default(), overheating: drone.inner.overheating.clone() };
+            let state_listener = StateListener::start_listening(options.local_address(options.state_port), sinks).await?;
+            *drone.inner.state_listener.get_mut() = Some(state_listener);
         }
 
         // want drone video?
         if let Some(video_tx) = &options.video_sender {
-            let video_listener = VideoListener::start_listening(video_tx.clone()).await?;
-            drone.inner.video_listener = Some(video_listener);
+            let video_listener = VideoListener::start_listening(options.local_address(options.video_port), video_tx.clone(), drone.inner.video_stats.clone(), options.video_receive_buffer_size).await?;
+            *drone.inner.video_listener.get_mut() = Some(video_listener);
         }
 
-        // expecting commands?
-        if let Some(command_rx) = options.command_receiver {
-            drone.inner.command_receiver = Some(Mutex::new(command_rx));
+        // tell drone to expect text SDK commands (not the private binary protocol)
+        if options.skip_command_handshake {
+            info!("[Tello] assuming drone is already in command mode");
+        }
+        else {
+            info!("[Tello] putting drone in command mode...");
+            drone.send_expect_ok("command").await?;
         }
 
-        // tell drone to expect text SDK commands (not the private binary protocol)
-        println!("[Tello] putting drone in command mode...");
-        drone.send_expect_ok("command").await?;
+        // check SDK version
+        if let Some((major, minor)) = options.require_sdk_version {
+            let version = drone.sdk_version().await?;
+            if parse_sdk_version(&version)? < (major, minor) {
+                return Err(TelloError::UnsupportedFirmware { version, required: format!("{major}.{minor}") });
+            }
+        }
+
+        // find out what it can do
+        if options.probe_capabilities {
+            let capabilities = drone.probe_capabilities().await;
+            info!("[Tello] capabilities: {capabilities:?}");
+            drone.inner.capabilities = Some(capabilities);
+        }
 
         // check battery
         let b = drone.battery().await?;
         if b < 10 {
-            println!("[Tello] WARNING low battery: {b}%");
+            warn!("[Tello] WARNING low battery: {b}%");
+            options.emit(TelloEvent::LowBattery { percent: b });
         }
         else {
-            println!("[Tello] battery: {b}%");  
+            info!("[Tello] battery: {b}%");  
+        }
+
+        drone.start_background_tasks().await;
+
+        // watch for leaving the drone's WiFi?
+        if let Some(wifi_tx) = &options.wifi_event_sender {
+            match &self.inner.ssid {
+                Some(ssid) => {
+                    let wifi_monitor = WiFiMonitor::start(ssid.clone(), drone.inner.wifi_lost.clone(), wifi_tx.clone());
+                    *drone.inner.wifi_monitor.get_mut() = Some(wifi_monitor);
+                }
+                None => warn!("[Tello] WARNING WiFi network not known, can't monitor it")
+            }
+        }
+
+        // already flying, eg left in the air by a previous session?
+        if drone.inner.state_listener.get_mut().is_some() {
+            match drone.wait_for_state(Duration::from_secs(1)).await {
+                Some(state) if state.is_flying() => {
+                    warn!("[Tello] WARNING drone is already airborne (height {}cm)", state.height);
+                    drone.inner.airborne_on_connect = true;
+                    *drone.inner.flight_status.get_mut().unwrap() = FlightStatus::Flying;
+                }
+                Some(_) => {}
+                None => warn!("[Tello] WARNING no state received, can't tell if drone is airborne")
+            }
         }
 
+        options.emit(TelloEvent::Connected);
         Ok(drone)
     } 
 }
 
 impl Tello<Connected> {
+    /// The socket for commands, unless it couldn't be opened again by 
+    /// `reopen_control_socket()`.
+    fn sock(&self) -> Result<Arc<UdpSocket>> {
+        self.inner.sock.lock().unwrap().clone()
+            .ok_or_else(|| TelloError::Generic { msg: "no socket for commands, reconnect".to_string() })
+    }
+
+    /// Starts the optional tasks sending commands in the background.
+    async fn start_background_tasks(&self) {
+        let inner = &self.inner;
+        let Ok(sock) = self.sock() else { return };
+
+        // keep remote control moving the drone smoothly?
+        if inner.options.repeat_remote_control {
+            let repeater = RemoteControlRepeater::start(sock.clone(), inner.command_lock.clone(), inner.remote_control.clone());
+            *inner.remote_control_repeater.lock().await = Some(repeater);
+        }
+
+        // stop the drone landing itself when left idle?
+        if inner.options.keep_alive {
+            let keep_alive = KeepAlive::start(sock, inner.command_lock.clone());
+            *inner.keep_alive.lock().await = Some(keep_alive);
+        }
+    }
+
+    /// Stops any tasks sending commands in the background.
+    async fn stop_background_tasks(&self) {
+        if let Some(keep_alive) = self.inner.keep_alive.lock().await.take() {
+            keep_alive.stop().await;
+        }
+
+        if let Some(repeater) = self.inner.remote_control_repeater.lock().await.take() {
+            repeater.stop().await;
+        }
+    }
+
+    /// Replaces the socket for commands with a new one on the same local 
+    /// port. The background tasks sharing the socket are stopped first, and
+    /// no command can be in progress, so the old socket is closed and the 
+    /// port free to bind again.
+    async fn reopen_control_socket(&self) -> Result<()> {
+        self.stop_background_tasks().await;
+
+        let reopened = {
+            let _command_lock = self.inner.command_lock.lock().await;
+            self.inner.sock.lock().unwrap().take();
+            open_control_socket(&self.inner.options).await
+                .map(|sock| *self.inner.sock.lock().unwrap() = Some(Arc::new(sock)))
+        };
+
+        self.start_background_tasks().await;
+        reopened
+    }
+
+    /// Reconnect after the link to the drone has dropped, eg a brief WiFi 
+    /// outage, with a new socket for commands. The state and video 
+    /// listeners and the command channel are left as they are.
+    ///
+    /// The drone is put back in command mode and the battery checked, as 
+    /// when first connecting.
+    ///
+    pub async fn reconnect(&self) -> Result<()> {
+        info!("[Tello] RECONNECT");
+
+        self.reopen_control_socket().await?;
+
+        if !self.inner.options.skip_command_handshake {
+            self.send_expect_ok("command").await?;
+        }
+
+        let b = self.battery().await?;
+        info!("[Tello] battery: {b}%");
+        Ok(())
+    }
+
     /// Disconnect from the drone.
     pub async fn disconnect(&self) -> Result<Tello<Disconnected>> {
-        println!("[Tello] DISCONNECT");
+        info!("[Tello] DISCONNECT");
+        self.inner.options.emit(TelloEvent::Disconnected);
+
+        self.stop_background_tasks().await;
+
+        // stop both, even if one failed
+        let mut stopped = Ok(());
+        if let Some(state_listener) = self.inner.state_listener.lock().await.take() {
+            stopped = state_listener.stop_listening().await;
+        }
+
+        if let Some(video_listener) = self.inner.video_listener.lock().await.take() {
+            stopped = stopped.and(video_listener.stop_listening().await);
+        }
+
+        if let Some(wifi_monitor) = self.inner.wifi_monitor.lock().await.take() {
+            wifi_monitor.stop().await;
+        }
+
+        stopped.map(|_| Tello { inner: Disconnected { ssid: self.inner.ssid.clone() } })
+    }
+
+    /// Disconnect from the drone, keeping the options so the same channels 
+    /// can be used again when reconnecting.
+    ///
+    /// ```no_run
+    /// # use tello_edu::{Tello, TelloOptions, Result};
+    /// # async fn reconnect() -> Result<()> {
+    /// let mut options = TelloOptions::default();
+    /// let mut state_receiver = options.with_state();
+    /// 
+    /// let drone = Tello::new().wait_for_wifi().await?.connect_with(options).await?;
+    /// let (drone, options) = drone.disconnect_keeping_options().await?;
+    ///
+    /// // state_receiver is still open, and will receive updates again...
+    /// let drone = drone.connect_with(options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn disconnect_keeping_options(&self) -> Result<(Tello<Disconnected>, TelloOptions)> {
+        let drone = self.disconnect().await?;
+        let mut options = self.inner.options.clone();
+        (options.state_port, options.video_port) = *self.inner.ports.lock().unwrap();
+        Ok((drone, options))
+    }
+
+    /// Whether the drone already appeared to be in the air when connected, 
+    /// eg if left flying by a previous session.
+    ///
+    /// *nb* Always `false` unless state updates were requested when 
+    /// connecting.
+    ///
+    pub fn was_airborne_on_connect(&self) -> bool {
+        self.inner.airborne_on_connect
+    }
+
+    /// Whether the drone is in the air, as far as the commands sent to it go 
+    /// - ie it's taking off, flying or landing.
+    pub fn is_airborne(&self) -> bool {
+        *self.inner.flight_status.lock().unwrap() != FlightStatus::Landed
+    }
+
+    fn set_flight_status(&self, status: FlightStatus) {
+        *self.inner.flight_status.lock().unwrap() = status;
+    }
+
+    /// Whether the drone supports an optional feature.
+    ///
+    /// *nb* Always `false` unless `TelloOptions::probe_capabilities` was set
+    /// when connecting.
+    ///
+    /// - `capability` The feature
+    ///
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.inner.capabilities.as_ref().is_some_and(|c| c.contains(&capability))
+    }
+
+    async fn probe_capabilities(&self) -> HashSet<Capability> {
+        let mut capabilities = HashSet::new();
+
+        let sdk_version = match self.send_with_timeout("sdk?", PROBE_TIMEOUT).await {
+            Ok(version) => parse_sdk_version(&version).ok(),
+            Err(_) => None
+        };
+        if sdk_version >= Some((2, 0)) {
+            capabilities.insert(Capability::StationMode);
+        }
+        if sdk_version >= Some((3, 0)) {
+            capabilities.insert(Capability::VideoConfig);
+        }
 
-        if let Some(state_listener) = &self.inner.state_listener {
-            state_listener.stop_listening().await?;
+        if self.probe("mid?").await {
+            capabilities.insert(Capability::MissionPads);
         }
+        if self.probe("EXT version?").await {
+            capabilities.insert(Capability::ExtExpansion);
+        }
+
+        // discard any responses that arrived too late
+        while self.recv_raw(PROBE_TIMEOUT / 4).await.is_ok() {}
+
+        capabilities
+    }
+
+    /// The optional features the drone's SDK version should support, 
+    /// without probing for them as `TelloOptions::probe_capabilities` does.
+    ///
+    /// *nb* Whether mission pads and the expansion board are actually 
+    /// there isn't checked, eg a RoboMaster TT may have no expansion board 
+    /// fitted.
+    ///
+    pub async fn capabilities(&self) -> Result<HashSet<Capability>> {
+        Capability::for_sdk_version(&self.sdk_version().await?)
+    }
 
-        if let Some(video_listener) = &self.inner.video_listener {
-            video_listener.stop_listening().await?;
+    /// Sends a harmless query, resolving to whether the drone understood it.
+    async fn probe(&self, query: &str) -> bool {
+        match self.send_with_timeout(query, PROBE_TIMEOUT).await {
+            Ok(response) => !response.starts_with("unknown command") && !response.starts_with("error"),
+            Err(_) => false
         }
+    }
+
+    /// The most recently received drone state, if any.
+    fn latest_state(&self) -> Option<TelloState> {
+        self.inner.latest_state.lock().unwrap().clone()
+    }
+
+    /// Whether state updates are being received.
+    async fn state_enabled(&self) -> bool {
+        self.inner.state_listener.lock().await.is_some()
+    }
 
-        Ok(Tello { inner: Disconnected })
+    /// Wait until a drone state has been received, up to the given time.
+    async fn wait_for_state(&self, timeout: Duration) -> Option<TelloState> {
+        let start = tokio::time::Instant::now();
+        loop {
+            if let Some(state) = self.latest_state() {
+                return Some(state);
+            }
+            if start.elapsed() >= timeout {
+                return None;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
     }
 
     /// Sends a command to the drone using the simple Tello UDP protocol, returning the reponse.
@@ -198,15 +662,108 @@ impl Tello<Connected> {
     ///
     /// Messages are plain ASCII text, eg command `forward 10` → response `ok`
     ///
+    /// Commands sent from several tasks at once are sent one at a time, each
+    /// waiting for the response to the one before, so responses are never 
+    /// mixed up. The exception is an emergency stop, which is sent 
+    /// immediately.
+    ///
+    /// Resolves to `TelloError::Timeout` if no response arrives within
+    /// `TelloOptions::command_timeout`.
+    ///
     /// - `command` the command to send, must be a valid Tello SDK command string
     /// 
     pub async fn send(&self, command: &str) -> Result<String> {
-        println!("[Tello] SEND {command}");
+        self.send_with_timeout(command, self.inner.options.command_timeout).await
+    }
+
+    /// Sends a command to the drone, returning the parsed response rather
+    /// than the raw text as `send()` does.
+    ///
+    /// Resolves to an error if the drone doesn't respond "ok" to an action,
+    /// or to `TelloError::ParseError` if a query's response can't be parsed.
+    ///
+    /// - `command` The command to send
+    ///
+    pub async fn send_command(&self, command: SdkCommand) -> Result<SdkResponse> {
+        let response = self.send(&command.to_sdk_string()).await?;
+        command.parse_response(&response)
+    }
+
+    /// Sends a command to the drone, returning the response, or 
+    /// `TelloError::Timeout` if none arrives in time.
+    ///
+    /// - `command` the command to send, must be a valid Tello SDK command string
+    /// - `timeout` How long to wait for the response
+    /// 
+    pub async fn send_with_timeout(&self, command: &str, timeout: Duration) -> Result<String> {
+        let mut last_command = self.inner.command_lock.lock().await;
+        self.log_command(command);
+        self.inner.remote_control.active.store(false, Ordering::Relaxed);
+        let response = self.exchange(command, timeout).await;
+        *last_command = tokio::time::Instant::now();
+        response.map_err(|err| self.wifi_error(err))
+    }
+
+    /// Adds a command to the log, if recording one.
+    fn log_command(&self, command: &str) {
+        if let Some(log) = &self.inner.options.command_log {
+            log.lock().unwrap().push((self.inner.command_log_started.elapsed(), redacted(command).into_owned()));
+        }
+    }
+
+    /// Sends the commands from a log again, at the same intervals as 
+    /// originally, eg to repeat a flight recorded with 
+    /// `TelloOptions::with_command_log()`.
+    ///
+    /// Stops with the error if any command fails, ie anything other than a
+    /// query or remote control isn't acknowledged with "ok". Commands 
+    /// setting WiFi credentials are skipped, with a warning, as the 
+    /// passwords aren't logged.
+    ///
+    /// - `log` The commands and the times they were sent
+    ///
+    pub async fn replay_log(&self, log: &[(Duration, String)]) -> Result<()> {
+        let Some((first_sent, _)) = log.first() else { return Ok(()) };
+        info!("[Tello] REPLAYING {} commands", log.len());
+
+        let start = tokio::time::Instant::now();
+        for (sent, command) in log {
+            tokio::time::sleep_until(start + sent.saturating_sub(*first_sent)).await;
 
-        let s = &self.inner.sock;
-        s.send(command.as_bytes()).await?;
+            if is_credential_command(command) {
+                warn!("[Tello] WARNING skipping \"{command}\", WiFi credentials can't be replayed");
+            }
+            else if command.starts_with("rc ") {
+                self.send_expect_nothing(command).await?;
+            }
+            else if command.ends_with('?') {
+                self.send(command).await?;
+            }
+            else {
+                self.send_expect_ok(command).await?;
+            }
+        }
+
+        Ok(())
+    }
 
-        let response = self.recv().await?;
+    /// Blames an error on the WiFi if the monitor has seen the host leave 
+    /// the drone's network, see `TelloOptions::with_wifi_monitor()`.
+    fn wifi_error(&self, err: TelloError) -> TelloError {
+        match err {
+            TelloError::IOError { .. } | TelloError::Timeout { .. } if self.inner.wifi_lost.load(Ordering::Relaxed) => TelloError::WiFiLost,
+            err => err
+        }
+    }
+
+    async fn exchange(&self, command: &str, timeout: Duration) -> Result<String> {
+        debug!("[Tello] SEND {}", redacted(command));
+        self.inner.options.emit(TelloEvent::CommandSent { command: redacted(command).into_owned() });
+
+        let s = self.sock()?;
+        s.send(command.as_bytes()).await.map_err(send_error)?;
+
+        let response = self.recv_within(command, timeout).await?;
 
         // the drone sends "forced stop" after "stop" after a delay which may
         // arrive after more commands have been sent
@@ -214,29 +771,95 @@ impl Tello<Connected> {
             self.on_forced_stop();
 
             // try again
-            self.recv().await
+            self.recv_within(command, timeout).await
         }
         else {
             Ok(response)
         }          
     }
 
+    async fn recv_within(&self, command: &str, timeout: Duration) -> Result<String> {
+        tokio::time::timeout(timeout, self.recv()).await
+            .map_err(|_| TelloError::Timeout { command: redacted(command).into_owned() })?
+    }
+
     async fn recv(&self) -> Result<String> {
-        let s = &self.inner.sock;
-        let mut buf = vec![0; 256];        
-        let n = s.recv(&mut buf).await?;
+        let s = self.sock()?;
+        let mut buf = vec![0; MAX_RESPONSE_CHUNK_SIZE];
+        let mut n = s.recv(&mut buf).await?;
+
+        // nearly always the whole response arrives at once, but a long one 
+        // may be split across datagrams of any size, so read on until it 
+        // ends with a newline or no more arrives
+        while buf[..n].last() != Some(&b'\n') && !UNTERMINATED_RESPONSES.iter().any(|r| r.as_bytes() == &buf[..n]) {
+            buf.resize(n + MAX_RESPONSE_CHUNK_SIZE, 0);
+            match tokio::time::timeout(RESPONSE_CONTINUATION_TIMEOUT, s.recv(&mut buf[n..])).await {
+                Ok(received) => n += received?,
+                Err(_) => break
+            }
+        }
 
         buf.truncate(n);
-        let r = String::from_utf8(buf)?;
+        let r = if self.inner.options.lossy_responses {
+            match String::from_utf8(buf) {
+                Ok(r) => r,
+                Err(err) => {
+                    let r = String::from_utf8_lossy(err.as_bytes()).into_owned();
+                    warn!("[Tello] WARNING response is not valid UTF-8, decoded as \"{r}\"");
+                    r
+                }
+            }
+        }
+        else {
+            String::from_utf8(buf)?
+        };
         let response = r.trim().to_string();
 
-        println!("[Tello] RECEIVED {response}");
+        debug!("[Tello] RECEIVED {response}");
+        self.inner.options.emit(TelloEvent::ResponseReceived { response: response.clone() });
 
         Ok(response)
     }
 
+    /// Sends arbitrary bytes to the drone, eg an experimental command that 
+    /// isn't otherwise supported.
+    ///
+    /// *nb* This bypasses the usual command/response handling, so any 
+    /// response is left unread and will be taken as the response to the 
+    /// next command unless read with `recv_raw()`.
+    ///
+    /// - `bytes` The data to send
+    ///
+    pub async fn send_bytes(&self, bytes: &[u8]) -> Result<()> {
+        let _command_lock = self.inner.command_lock.lock().await;
+
+        debug!("[Tello] SEND {} bytes", bytes.len());
+
+        let s = self.sock()?;
+        s.send(bytes).await?;
+
+        Ok(())
+    }
+
+    /// Receives the next message from the drone, without sending anything 
+    /// first.
+    ///
+    /// For experimenting with the protocol, eg reading unsolicited messages 
+    /// such as a stray `ok` after `rc`. Resolves to `TelloError::Timeout` if
+    /// nothing arrives in time.
+    ///
+    /// *nb* Any message read here is no longer available as the response to
+    /// a command.
+    ///
+    /// - `timeout` How long to wait for a message
+    ///
+    pub async fn recv_raw(&self, timeout: Duration) -> Result<String> {
+        let _command_lock = self.inner.command_lock.lock().await;
+        self.recv_within("(none)", timeout).await
+    }
+
     fn on_forced_stop(&self) {
-        println!("[Tello] FORCED STOP");
+        warn!("[Tello] FORCED STOP");
     }
 
     /// Sends a command, resolving to an error if the response is not "ok"
@@ -244,17 +867,7 @@ impl Tello<Connected> {
     /// - `command` the command to send, must be a valid Tello SDK command string
     /// 
     pub async fn send_expect_ok(&self, command: &str) -> Result<()> {
-        match self.send(command).await {
-            Ok(response) => {
-                if response == "ok" {
-                    Ok(())
-                }
-                else {
-                    Err(TelloError::from_not_ok_response(response))
-                }
-            }
-            Err(err) => Err(err)
-        }
+        expect_ok(self.send(command).await?)
     }
 
     /// Sends a command with a single value, resolving to an error if the 
@@ -264,57 +877,179 @@ impl Tello<Connected> {
     /// - `value` the value to append to the command
     /// 
     pub async fn send_value_expect_ok<T: std::fmt::Display>(&self, command: &str, value: T) -> Result<()> {
-        match self.send(&format!("{command} {value}")).await {
-            Ok(response) => {
-                if response == "ok" {
-                    Ok(())
-                }
-                else {
-                    Err(TelloError::from_not_ok_response(response))
-                }
-            }
-            Err(err) => Err(err)
-        }
+        expect_ok(self.send(&format!("{command} {value}")).await?)
     }
 
-    /// Sends a command, expecting no response at all from the drone.
+    /// Sends a command with a single value, resolving to an error if the 
+    /// response is not "ok" or does not arrive in time.
     ///
     /// - `command` the command to send, must be a valid Tello SDK command string
+    /// - `value` the value to append to the command
+    /// - `timeout` How long to wait for the response
     /// 
-    pub async fn send_expect_nothing(&self, command: &str) -> Result<()> {
-        println!("[Tello] SEND {command}");
-
-        let s = &self.inner.sock;
-        s.send(command.as_bytes()).await?;
+    pub async fn send_value_expect_ok_timeout<T: std::fmt::Display>(&self, command: &str, value: T, timeout: Duration) -> Result<()> {
+        expect_ok(self.send_with_timeout(&format!("{command} {value}"), timeout).await?)
+    }
 
-        Ok(())
+    /// Sends an `EXT` command to the RoboMaster TT expansion board, 
+    /// resolving to an error unless it's acknowledged, with either "ok" or 
+    /// the part of the board it's for then "ok", eg "led ok" for 
+    /// `led 255 0 0` or "matrix ok" for `mled ...`. Use `ext_send()` for 
+    /// any other acknowledgement, or `ext_query()` for a reading.
+    ///
+    /// - `subcommand` the command to send, without the `EXT` prefix, eg `led 255 0 0`
+    ///
+    pub async fn ext(&self, subcommand: &str) -> Result<()> {
+        let response = self.ext_query(subcommand).await?;
+        if is_ext_ack(subcommand, &response) {
+            Ok(())
+        }
+        else {
+            Err(TelloError::from_not_ok_response(response))
+        }
     }
 
-    /// Sends a command, expecting a response that can be parsed as type `T` from the drone.
+    /// Set the colour of the RoboMaster TT expansion board's top LED.
     ///
-    /// - `command` the command to send, must be a valid Tello SDK command string
-    /// 
-    pub async fn send_expect<T: std::str::FromStr>(&self, command: &str) -> Result<T> {
-        let r = self.send(command).await?;
-        let v = r.parse::<T>().map_err(|_| TelloError::ParseError { msg: format!("unexpected response: \"{r}\"")})?;
-        Ok(v)
+    /// - `r` Red, 0-255
+    /// - `g` Green, 0-255
+    /// - `b` Blue, 0-255
+    ///
+    pub async fn set_led_rgb(&self, r: u8, g: u8, b: u8) -> Result<()> {
+        self.ext(&format!("led {r} {g} {b}")).await
     }
 
-    /// The unique drone serial number.
-    pub async fn serial_number(&self) -> Result<String> {
-        self.send("sn?").await
+    /// Make the RoboMaster TT expansion board's top LED breathe, ie fade in
+    /// and out.
+    ///
+    /// - `r` Red, 0-255
+    /// - `g` Green, 0-255
+    /// - `b` Blue, 0-255
+    /// - `hz` Breaths per second, 0.1-2.5
+    ///
+    pub async fn set_led_breath(&self, r: u8, g: u8, b: u8, hz: f32) -> Result<()> {
+        check_range(hz, LED_FREQUENCY_RANGE)?;
+        self.ext(&format!("led br {hz} {r} {g} {b}")).await
     }
 
-    /// The Tello SDK version.
-    pub async fn sdk_version(&self) -> Result<String> {
-        self.send("sdk?").await
+    /// Scroll some text across the RoboMaster TT expansion board's LED 
+    /// matrix.
+    ///
+    /// - `color` `r` red, `b` blue, `p` purple or `0` off
+    /// - `direction` Scroll `l` left, `r` right, `u` up or `d` down
+    /// - `text` Up to 70 characters
+    ///
+    pub async fn display_matrix_string(&self, color: char, direction: char, text: &str) -> Result<()> {
+        if !matches!(color, 'r' | 'b' | 'p' | '0') {
+            return Err(TelloError::Generic { msg: format!("matrix color must be r, b, p or 0, not '{color}'") });
+        }
+        if !matches!(direction, 'l' | 'r' | 'u' | 'd') {
+            return Err(TelloError::Generic { msg: format!("matrix direction must be l, r, u or d, not '{direction}'") });
+        }
+        if text.is_empty() || text.chars().count() > MAX_MATRIX_TEXT_LENGTH {
+            return Err(TelloError::Generic { msg: format!("matrix text must be 1-{MAX_MATRIX_TEXT_LENGTH} characters") });
+        }
+        self.ext(&format!("mled {direction} {color} {MATRIX_SCROLL_FREQUENCY} {text}")).await
     }
 
-    /// The drone battery level as a percentage.
-    pub async fn battery(&self) -> Result<u8> {
+    /// Sends an `EXT` command to the RoboMaster TT expansion board, 
+    /// resolving to an error if the response is not exactly the expected 
+    /// acknowledgement, see `ext()` for the usual ones.
+    ///
+    /// - `command` the command to send, without the `EXT` prefix, eg `led 255 0 0`
+    /// - `expected_ack` the response expected on success, eg `led ok`
+    ///
+    pub async fn ext_send(&self, command: &str, expected_ack: &str) -> Result<()> {
+        let response = self.ext_query(command).await?;
+        if response == expected_ack {
+            Ok(())
+        }
+        else {
+            Err(TelloError::from_not_ok_response(response))
+        }
+    }
+
+    /// Sends an `EXT` command to the RoboMaster TT expansion board, 
+    /// returning the response, eg a sensor reading.
+    ///
+    /// - `command` the command to send, without the `EXT` prefix, eg `tof?`
+    ///
+    pub async fn ext_query(&self, command: &str) -> Result<String> {
+        self.send(&format!("EXT {command}")).await
+    }
+
+    /// Sends a command, expecting no response at all from the drone.
+    ///
+    /// - `command` the command to send, must be a valid Tello SDK command string
+    /// 
+    pub async fn send_expect_nothing(&self, command: &str) -> Result<()> {
+        let mut last_command = self.inner.command_lock.lock().await;
+        self.log_command(command);
+
+        debug!("[Tello] SEND {}", redacted(command));
+        self.inner.options.emit(TelloEvent::CommandSent { command: redacted(command).into_owned() });
+
+        let s = self.sock()?;
+        s.send(command.as_bytes()).await.map_err(|err| self.wifi_error(send_error(err)))?;
+
+        *last_command = tokio::time::Instant::now();
+        Ok(())
+    }
+
+    /// Sends a command, expecting a response that can be parsed as type `T` from the drone.
+    ///
+    /// - `command` the command to send, must be a valid Tello SDK command string
+    /// 
+    pub async fn send_expect<T: std::str::FromStr>(&self, command: &str) -> Result<T> {
+        let r = self.send(command).await?;
+        let v = r.parse::<T>().map_err(|_| TelloError::ParseError { msg: format!("unexpected response: \"{r}\"")})?;
+        Ok(v)
+    }
+
+    /// The unique drone serial number.
+    pub async fn serial_number(&self) -> Result<String> {
+        self.send("sn?").await
+    }
+
+    /// The Tello SDK version.
+    pub async fn sdk_version(&self) -> Result<String> {
+        self.send("sdk?").await
+    }
+
+    /// The drone hardware, eg "TELLO" or "RMTT" for a RoboMaster TT.
+    ///
+    /// *nb* Older firmware doesn't answer, so this resolves to 
+    /// `TelloError::Timeout` after `TelloOptions::command_timeout`.
+    ///
+    pub async fn hardware(&self) -> Result<String> {
+        self.send("hardware?").await
+    }
+
+    /// The drone firmware version, eg "01.04.92.01".
+    ///
+    /// *nb* Not in the SDK guides, and older firmware doesn't answer, so 
+    /// this resolves to `TelloError::Timeout` after 
+    /// `TelloOptions::command_timeout`.
+    ///
+    pub async fn firmware_version(&self) -> Result<String> {
+        self.send("firmware?").await
+    }
+
+    /// The drone battery level as a percentage.
+    pub async fn battery(&self) -> Result<u8> {
         self.send_expect::<u8>("battery?").await
     }
 
+    /// The drone battery level as a percentage, from the latest state update
+    /// rather than asking the drone.
+    ///
+    /// `None` unless state updates were requested when connecting (or none 
+    /// have been received yet).
+    ///
+    pub fn cached_battery(&self) -> Option<u8> {
+        self.latest_state().map(|s| s.battery)
+    }
+
     /// The WiFi signal to noise ratio as a percentage.
     pub async fn wifi_signal_to_noise_ratio(&self) -> Result<u8> {
         self.send_expect::<u8>("wifi?").await
@@ -325,22 +1060,216 @@ impl Tello<Connected> {
         self.send_expect::<u16>("time?").await
     }
 
-    /// Immediately stop all motors.
+    /// The drone attitude, `(pitch, roll, yaw)` in degrees, requested 
+    /// directly from the drone.
+    pub async fn attitude(&self) -> Result<(i16, i16, i16)> {
+        parse_attitude(&self.send("attitude?").await?)
+    }
+
+    /// The lowest and highest temperatures, in Celsius, requested directly
+    /// from the drone.
+    ///
+    /// Unlike the state updates this also works in station mode.
+    ///
+    pub async fn temperature(&self) -> Result<(i16, i16)> {
+        parse_temperature(&self.send("temp?").await?)
+    }
+
+    /// The barometer measurement, in m, requested directly from the drone.
+    pub async fn barometer(&self) -> Result<f32> {
+        let response = self.send("baro?").await?;
+        parse_value(response.trim_end_matches('m'), &response)
+    }
+
+    /// The distance measured by the downward time of flight sensor, in cm, 
+    /// requested directly from the drone.
+    pub async fn time_of_flight(&self) -> Result<u16> {
+        parse_distance(&self.send("tof?").await?)
+    }
+
+    /// The height above the launch point, in cm, requested directly from 
+    /// the drone.
+    ///
+    /// *nb* The drone reports this to the nearest 10cm
+    ///
+    pub async fn height(&self) -> Result<i16> {
+        parse_distance(&self.send("height?").await?)
+    }
+
+    /// Gathers information about the drone into a report for pasting into 
+    /// bug reports.
+    ///
+    /// Any query that fails is shown as "N/A" rather than failing the whole 
+    /// report.
+    ///
+    pub async fn diagnostics(&self) -> Result<String> {
+        let queries = [
+            ("serial number", "sn?", ""),
+            ("SDK version", "sdk?", ""),
+            ("hardware", "hardware?", ""),
+            ("battery", "battery?", "%"),
+            ("WiFi SNR", "wifi?", "%"),
+            ("speed", "speed?", " cm/s"),
+            ("flight time", "time?", "")
+        ];
+
+        let mut report = format!("tello-edu {}\n", env!("CARGO_PKG_VERSION"));
+        for (name, query, units) in queries {
+            let value = match self.send_with_timeout(query, DIAGNOSTICS_QUERY_TIMEOUT).await {
+                Ok(response) => format!("{response}{units}"),
+                Err(err) => {
+                    warn!("[Tello] diagnostics query {query} failed ({err})");
+                    "N/A".to_string()
+                }
+            };
+            report.push_str(&format!("{name}: {value}\n"));
+        }
+
+        Ok(report)
+    }
+
+    /// Query the speed, flight time and battery level in one go.
+    ///
+    /// Useful for a periodic snapshot when state updates are not available,
+    /// eg in station mode. The queries are sent one after another.
+    ///
+    pub async fn poll_telemetry(&self) -> Result<Telemetry> {
+        let speed = self.speed().await?;
+        let flight_time = self.flight_time().await?;
+        let battery = self.battery().await?;
+        Ok(Telemetry { speed, flight_time, battery })
+    }
+
+    /// Arm `emergency_stop()`, which then works for the next 3 seconds.
+    pub fn arm_emergency_stop(&self) {
+        info!("[Tello] emergency stop armed");
+        *self.inner.emergency_stop_armed_at.lock().unwrap() = Some(std::time::Instant::now());
+    }
+
+    /// Whether `emergency_stop()` would stop the motors now.
+    fn is_emergency_stop_armed(&self) -> bool {
+        matches!(*self.inner.emergency_stop_armed_at.lock().unwrap(), Some(t) if t.elapsed() <= EMERGENCY_STOP_ARMED_TIME)
+    }
+
+    /// Immediately stop all motors, if armed with `arm_emergency_stop()` in
+    /// the last 3 seconds, so it can't be triggered by accident. Fails with
+    /// `TelloError::Generic` without sending anything if not armed.
+    ///
+    /// *nb* Earlier versions didn't need arming first - 
+    /// `emergency_stop_unchecked()` works as this used to.
     ///
     /// warning! this will make the drone drop like a brick!
     ///
     pub async fn emergency_stop(&self) -> Result<()> {
-        self.send_expect_nothing("emergency").await
+        let armed_at = self.inner.emergency_stop_armed_at.lock().unwrap().take();
+        match armed_at {
+            Some(t) if t.elapsed() <= EMERGENCY_STOP_ARMED_TIME => self.emergency_stop_unchecked().await,
+            _ => Err(emergency_stop_not_armed())
+        }
+    }
+
+    /// Immediately stop all motors, without needing to be armed first.
+    ///
+    /// warning! this will make the drone drop like a brick!
+    ///
+    pub async fn emergency_stop_unchecked(&self) -> Result<()> {
+        // don't wait for any command in progress to finish
+        self.inner.remote_control.active.store(false, Ordering::Relaxed);
+        debug!("[Tello] SEND emergency");
+        self.inner.options.emit(TelloEvent::CommandSent { command: "emergency".to_string() });
+        self.sock()?.send(b"emergency").await?;
+
+        self.set_flight_status(FlightStatus::Landed);
+        self.update_position(|p| PositionEstimate { z: 0.0, ..p });
+        Ok(())
     }
 
     /// Take off and hover.
+    ///
+    /// *nb* Ignored if the drone is already taking off, flying or landing, 
+    /// eg when the take off button is pressed repeatedly.
+    ///
     pub async fn take_off(&self) -> Result<()> {
-        self.send_expect_ok("takeoff").await
+        {
+            let mut status = self.inner.flight_status.lock().unwrap();
+            if *status != FlightStatus::Landed {
+                info!("[Tello] drone is {:?}, take off ignored", *status);
+                return Ok(());
+            }
+            *status = FlightStatus::TakingOff;
+        }
+
+        if let Err(err) = self.send_expect_ok("takeoff").await {
+            self.set_flight_status(FlightStatus::Landed);
+            return Err(err);
+        }
+        self.set_flight_status(FlightStatus::Flying);
+
+        let z = self.latest_state().map(|s| s.height as f32).unwrap_or(TAKE_OFF_HEIGHT);
+        self.update_position(|p| PositionEstimate { z, ..p });
+        Ok(())
+    }
+
+    /// Take off, then wait for the drone to settle into a steady hover.
+    ///
+    /// Resolves to the height reached in cm, which varies with the surface
+    /// and calibration. If the height doesn't settle within 5s the latest 
+    /// height is returned anyway, or `TelloError::Timeout` if there is none.
+    ///
+    /// *nb* Requires state updates, see `TelloOptions::with_state()`
+    ///
+    pub async fn take_off_reporting(&self) -> Result<i16> {
+        if !self.state_enabled().await {
+            return Err(TelloError::StateNotEnabled);
+        }
+
+        self.take_off().await?;
+
+        let start = tokio::time::Instant::now();
+        let mut last_height:Option<i16> = None;
+        let mut steady_count = 0;
+        loop {
+            sleep(TAKE_OFF_POLL_INTERVAL).await;
+
+            let height = self.latest_state().map(|s| s.height);
+
+            match (height, last_height) {
+                (Some(h), Some(last_h)) if (h - last_h).abs() <= HOVER_HEIGHT_TOLERANCE => steady_count += 1,
+                _ => steady_count = 0
+            }
+            last_height = height;
+
+            // steady for a while, or given up waiting
+            if steady_count >= TAKE_OFF_STEADY_CHECKS || start.elapsed() >= TAKE_OFF_SETTLE_TIMEOUT {
+                let height = height.ok_or_else(|| TelloError::Timeout { command: "takeoff".to_string() })?;
+                info!("[Tello] hovering at {height}cm");
+                return Ok(height);
+            }
+        }
     }
 
     /// Land and stop motors.
+    ///
+    /// *nb* Ignored if the drone is already landing.
+    ///
     pub async fn land(&self) -> Result<()> {
-        self.send_expect_ok("land").await
+        let previous_status = {
+            let mut status = self.inner.flight_status.lock().unwrap();
+            if *status == FlightStatus::Landing {
+                info!("[Tello] drone is already landing, land ignored");
+                return Ok(());
+            }
+            std::mem::replace(&mut *status, FlightStatus::Landing)
+        };
+
+        if let Err(err) = self.send_expect_ok("land").await {
+            self.set_flight_status(previous_status);
+            return Err(err);
+        }
+        self.set_flight_status(FlightStatus::Landed);
+
+        self.update_position(|p| PositionEstimate { z: 0.0, ..p });
+        Ok(())
     }
 
     /// The drone speed in cm/s, requested directly from the drone.
@@ -353,6 +1282,7 @@ impl Tello<Connected> {
     /// - `speed` Desired speed, 10-100 cm/s
     ///
     pub async fn set_speed(&self, speed: u8) -> Result<()> {
+        check_range(speed, SPEED_RANGE)?;
         self.send_value_expect_ok("speed", speed).await
     }
 
@@ -361,30 +1291,91 @@ impl Tello<Connected> {
     /// - `duration` The time to wait
     ///
     pub async fn wait(&self, duration:Duration) -> Result<()> {
-        println!("[Tello] waiting for {duration:#?}");
+        info!("[Tello] waiting for {duration:#?}");
         sleep(duration).await;
         Ok(())
     }    
 
     /// Stop and hover in place.
+    ///
+    /// Sends the SDK `stop` command and waits for the drone to acknowledge
+    /// it, which interrupts any movement command in progress. See also 
+    /// `hover()`.
     pub async fn stop(&self) -> Result<()> {
         // will also trigger a "forced stop" response
         self.send_expect_ok("stop").await
     }
 
+    /// Hover in place by zeroing the remote control velocities.
+    ///
+    /// Unlike `stop()` this just sends `rc 0 0 0 0` and returns immediately, 
+    /// without waiting for any response, so it suits control loops that 
+    /// want an explicit hold.
+    pub async fn hover(&self) -> Result<()> {
+        self.remote_control(0, 0, 0, 0).await
+    }
+
     /// Turn clockwise.
     ///
     /// - `degrees` Angle in degrees 1-360°
     ///
     pub async fn turn_clockwise(&self, degrees: u16) -> Result<()> {
-        self.send_value_expect_ok("cw", degrees).await   
+        check_range(degrees, TURN_DEGREES_RANGE)?;
+        self.send_value_expect_ok("cw", degrees).await?;
+        self.update_position(|p| p.turned(-(degrees as f32)));
+        Ok(())
     }
 
     /// Turn counter-clockwise.
     ///
     /// - `degrees` Angle in degrees 1-360°
     pub async fn turn_counterclockwise(&self, degrees: u16) -> Result<()> {
-        self.send_value_expect_ok("ccw", degrees).await   
+        check_range(degrees, TURN_DEGREES_RANGE)?;
+        self.send_value_expect_ok("ccw", degrees).await?;
+        self.update_position(|p| p.turned(degrees as f32));
+        Ok(())
+    }
+
+    /// Turn to face a heading, ie yaw, by the shortest way round, turning 
+    /// again if the first turn falls short or overshoots.
+    ///
+    /// *nb* Requires state updates, ie `state_receiver` from 
+    /// `TelloOptions::with_state()`, and nothing else should be reading 
+    /// from it meanwhile. The heading is relative to the drone's yaw when 
+    /// it started up, which drifts over time.
+    ///
+    /// - `heading` The yaw to turn to in degrees, eg -90 or 270 for a 
+    ///   quarter turn counter-clockwise from the start
+    /// - `state_receiver` The state updates
+    ///
+    pub async fn turn_to_heading(&self, heading: i16, state_receiver: &mut TelloStateReceiver) -> Result<()> {
+        for _ in 0..TURN_TO_HEADING_ATTEMPTS {
+            // only interested in where it's facing now
+            while state_receiver.try_recv().is_ok() {}
+            let yaw = state_receiver.recv().await.ok_or(TelloError::StateNotEnabled)?.yaw;
+
+            let turn = shortest_turn(yaw, heading);
+            if turn.abs() <= TURN_TO_HEADING_TOLERANCE {
+                return Ok(());
+            }
+            else if turn > 0 {
+                self.turn_clockwise(turn as u16).await?;
+            }
+            else {
+                self.turn_counterclockwise(turn.unsigned_abs()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move straight in the given direction, eg one chosen at runtime.
+    ///
+    /// - `direction` Which way to move
+    /// - `distance` Distance to travel, 20-500 cm
+    ///
+    pub async fn move_in(&self, direction: Direction, distance: u16) -> Result<()> {
+        self.send_move(direction, distance, None).await
     }
 
     /// Move straight up.
@@ -392,7 +1383,16 @@ impl Tello<Connected> {
     /// - `distance` Distance to travel, 20-500 cm
     ///
     pub async fn move_up(&self, distance: u16) -> Result<()> {
-        self.send_value_expect_ok("up", distance).await
+        self.move_in(Direction::Up, distance).await
+    }
+
+    /// Move straight up, with a timeout for this command only.
+    ///
+    /// - `distance` Distance to travel, 20-500 cm
+    /// - `timeout` How long to wait for the drone to finish the move
+    ///
+    pub async fn move_up_timeout(&self, distance: u16, timeout: Duration) -> Result<()> {
+        self.send_move(Direction::Up, distance, Some(timeout)).await
     }
 
     /// Move straight down.
@@ -400,7 +1400,16 @@ impl Tello<Connected> {
     /// - `distance` Distance to travel, 20-500 cm
     ///
     pub async fn move_down(&self, distance: u16) -> Result<()> {
-        self.send_value_expect_ok("down", distance).await
+        self.move_in(Direction::Down, distance).await
+    }
+
+    /// Move straight down, with a timeout for this command only.
+    ///
+    /// - `distance` Distance to travel, 20-500 cm
+    /// - `timeout` How long to wait for the drone to finish the move
+    ///
+    pub async fn move_down_timeout(&self, distance: u16, timeout: Duration) -> Result<()> {
+        self.send_move(Direction::Down, distance, Some(timeout)).await
     }
     
     /// Move straight left.
@@ -408,7 +1417,16 @@ impl Tello<Connected> {
     /// - `distance` Distance to travel, 20-500 cm
     ///
     pub async fn move_left(&self, distance: u16) -> Result<()> {
-        self.send_value_expect_ok("left", distance).await
+        self.move_in(Direction::Left, distance).await
+    }
+
+    /// Move straight left, with a timeout for this command only.
+    ///
+    /// - `distance` Distance to travel, 20-500 cm
+    /// - `timeout` How long to wait for the drone to finish the move
+    ///
+    pub async fn move_left_timeout(&self, distance: u16, timeout: Duration) -> Result<()> {
+        self.send_move(Direction::Left, distance, Some(timeout)).await
     }
     
     /// Move straight right.
@@ -416,7 +1434,16 @@ impl Tello<Connected> {
     /// - `distance` Distance to travel, 20-500 cm
     ///
     pub async fn move_right(&self, distance: u16) -> Result<()> {
-        self.send_value_expect_ok("right", distance).await
+        self.move_in(Direction::Right, distance).await
+    }
+
+    /// Move straight right, with a timeout for this command only.
+    ///
+    /// - `distance` Distance to travel, 20-500 cm
+    /// - `timeout` How long to wait for the drone to finish the move
+    ///
+    pub async fn move_right_timeout(&self, distance: u16, timeout: Duration) -> Result<()> {
+        self.send_move(Direction::Right, distance, Some(timeout)).await
     }
     
     /// Move straight forwards.
@@ -424,7 +1451,16 @@ impl Tello<Connected> {
     /// - `distance` Distance to travel, 20-500 cm
     ///
     pub async fn move_forward(&self, distance: u16) -> Result<()> {
-        self.send_value_expect_ok("forward", distance).await
+        self.move_in(Direction::Forward, distance).await
+    }
+
+    /// Move straight forwards, with a timeout for this command only.
+    ///
+    /// - `distance` Distance to travel, 20-500 cm
+    /// - `timeout` How long to wait for the drone to finish the move
+    ///
+    pub async fn move_forward_timeout(&self, distance: u16, timeout: Duration) -> Result<()> {
+        self.send_move(Direction::Forward, distance, Some(timeout)).await
     }
     
     /// Move straight backwards.
@@ -432,7 +1468,231 @@ impl Tello<Connected> {
     /// - `distance` Distance to travel, 20-500 cm
     ///
     pub async fn move_back(&self, distance: u16) -> Result<()> {
-        self.send_value_expect_ok("back", distance).await
+        self.move_in(Direction::Back, distance).await
+    }
+
+    /// Move straight backwards, with a timeout for this command only.
+    ///
+    /// - `distance` Distance to travel, 20-500 cm
+    /// - `timeout` How long to wait for the drone to finish the move
+    ///
+    pub async fn move_back_timeout(&self, distance: u16, timeout: Duration) -> Result<()> {
+        self.send_move(Direction::Back, distance, Some(timeout)).await
+    }
+
+    /// Fly in a straight line to a point relative to the current position.
+    ///
+    /// - `x` Distance forwards in cm, -500 to 500
+    /// - `y` Distance to the left in cm, -500 to 500
+    /// - `z` Distance up in cm, -500 to 500
+    /// - `speed` Speed in cm/s, 10 to 100
+    ///
+    /// *nb* The drone won't move less than 20cm, so at least one of `x`, `y` 
+    /// and `z` must be outside -20 to 20
+    ///
+    pub async fn go(&self, x: i16, y: i16, z: i16, speed: u8) -> Result<()> {
+        check_coordinates(&[x, y, z])?;
+        check_not_too_close(x, y, z)?;
+        check_range(speed, SPEED_RANGE)?;
+
+        let (forwards, left, up) = (x as f32, y as f32, z as f32);
+        self.check_bounds(|p| p.moved(forwards, left, up)).await?;
+        self.send_expect_ok(&format!("go {x} {y} {z} {speed}")).await?;
+        self.update_position(|p| p.moved(forwards, left, up));
+        Ok(())
+    }
+
+    /// Fly in a straight line to a point relative to the current position,
+    /// as `go()`, then wait until the state updates show the drone has come
+    /// to a stop there.
+    ///
+    /// Resolves to `TelloError::Timeout` if the drone is still moving well 
+    /// after it should have arrived.
+    ///
+    /// *nb* Requires state updates, ie `state_receiver` from 
+    /// `TelloOptions::with_state()`, and nothing else should be reading 
+    /// from it meanwhile
+    ///
+    /// - `x` Distance forwards in cm, -500 to 500
+    /// - `y` Distance to the left in cm, -500 to 500
+    /// - `z` Distance up in cm, -500 to 500
+    /// - `speed` Speed in cm/s, 10 to 100
+    /// - `state_receiver` The state updates
+    ///
+    pub async fn fly_to(&self, x: i16, y: i16, z: i16, speed: u8, state_receiver: &mut TelloStateReceiver) -> Result<()> {
+        // only interested in what happens from now on
+        while state_receiver.try_recv().is_ok() {}
+
+        match self.go(x, y, z, speed).await {
+            // a long move can outlast the command timeout
+            Err(TelloError::Timeout { .. }) => warn!("[Tello] WARNING go timed out, waiting for the drone to stop"),
+            result => result?
+        }
+
+        // allow for the whole move, in case it was only just starting
+        let distance = ((x as f32).powi(2) + (y as f32).powi(2) + (z as f32).powi(2)).sqrt();
+        let settle_timeout = Duration::from_secs_f32(distance / speed as f32) + FLY_TO_SETTLE_MARGIN;
+
+        let settled = tokio::time::timeout(settle_timeout, async {
+            let mut steady_count = 0;
+            while steady_count < FLY_TO_STEADY_UPDATES {
+                let state = state_receiver.recv().await.ok_or(TelloError::StateNotEnabled)?;
+                let v = state.velocity;
+                let stopped = [v.x, v.y, v.z].iter().all(|v| v.abs() <= FLY_TO_STOPPED_VELOCITY);
+                steady_count = if stopped { steady_count + 1 } else { 0 };
+            }
+            Ok(())
+        }).await;
+
+        settled.map_err(|_| TelloError::Timeout { command: format!("go {x} {y} {z} {speed}") })?
+    }
+
+    /// Fly an arc through one point to another, both relative to the 
+    /// current position, with x forwards, y to the left and z up.
+    ///
+    /// - `x1`, `y1`, `z1` The point to pass through in cm, each -500 to 500
+    /// - `x2`, `y2`, `z2` The point to finish at in cm, each -500 to 500
+    /// - `speed` Speed in cm/s, 10 to 60
+    ///
+    /// *nb* The drone also rejects arcs with a radius outside 0.5 to 10m
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub async fn curve(&self, x1: i16, y1: i16, z1: i16, x2: i16, y2: i16, z2: i16, speed: u8) -> Result<()> {
+        check_coordinates(&[x1, y1, z1, x2, y2, z2])?;
+        check_range(speed, CURVE_SPEED_RANGE)?;
+        if in_line((x1, y1, z1), (x2, y2, z2)) {
+            return Err(TelloError::OutOfRange);
+        }
+
+        let (forwards, left, up) = (x2 as f32, y2 as f32, z2 as f32);
+        self.check_bounds(|p| p.moved(forwards, left, up)).await?;
+        self.send_expect_ok(&curve_command((x1, y1, z1), (x2, y2, z2), speed)).await?;
+        self.update_position(|p| p.moved(forwards, left, up));
+        Ok(())
+    }
+
+    /// Fly from one mission pad to another, and turn to face a given yaw 
+    /// on arrival.
+    ///
+    /// *nb* Mission pad detection must be enabled first, see 
+    /// `enable_mission_pads()`
+    ///
+    /// - `x`, `y`, `z` Position in cm relative to the first pad, each -500 to 500
+    /// - `speed` Speed in cm/s, 10 to 100
+    /// - `yaw` Heading in degrees to turn to, relative to the second pad
+    /// - `mid1` The pad to fly from, 1 to 8
+    /// - `mid2` The pad to fly to, 1 to 8
+    ///
+    /// *nb* Unlike other moves, this isn't checked against the flight 
+    /// bounds, and doesn't update the position estimate, as where the pads 
+    /// are relative to the take off point isn't known. Later moves are 
+    /// still checked, but from where the drone was before jumping.
+    ///
+    #[allow(clippy::too_many_arguments)]
+    pub async fn jump(&self, x: i16, y: i16, z: i16, speed: u8, yaw: i16, mid1: u8, mid2: u8) -> Result<()> {
+        check_coordinates(&[x, y, z])?;
+        check_range(speed, SPEED_RANGE)?;
+        if !MISSION_PAD_IDS.contains(&mid1) || !MISSION_PAD_IDS.contains(&mid2) {
+            return Err(TelloError::OutOfRange);
+        }
+
+        self.send_expect_ok(&jump_command((x, y, z), speed, yaw, (mid1, mid2))).await
+    }
+
+    async fn send_move(&self, direction: Direction, distance: u16, timeout: Option<Duration>) -> Result<()> {
+        let keyword = direction.keyword();
+        let distance = if distance < MIN_MOVE_DISTANCE {
+            match self.inner.options.small_moves {
+                SmallMovePolicy::Reject => distance,
+                SmallMovePolicy::ClampUp => {
+                    info!("[Tello] {keyword} {distance}cm is too small, moving {MIN_MOVE_DISTANCE}cm instead");
+                    MIN_MOVE_DISTANCE
+                }
+                SmallMovePolicy::Ignore => {
+                    warn!("[Tello] WARNING {keyword} {distance}cm is too small, ignored");
+                    return Ok(());
+                }
+            }
+        }
+        else {
+            distance
+        };
+        check_range(distance, MOVE_DISTANCE_RANGE)?;
+
+        let d = distance as f32;
+        let (forwards, left, up) = match direction {
+            Direction::Forward => (d, 0.0, 0.0),
+            Direction::Back => (-d, 0.0, 0.0),
+            Direction::Left => (0.0, d, 0.0),
+            Direction::Right => (0.0, -d, 0.0),
+            Direction::Up => (0.0, 0.0, d),
+            Direction::Down => (0.0, 0.0, -d)
+        };
+        self.check_bounds(|p| p.moved(forwards, left, up)).await?;
+
+        let send = async {
+            match timeout {
+                Some(timeout) => self.send_value_expect_ok_timeout(keyword, distance, timeout).await,
+                None => self.send_value_expect_ok(keyword, distance).await
+            }
+        };
+        self.confirm_move(&format!("{keyword} {distance}"), send).await?;
+
+        self.update_position(|p| p.moved(forwards, left, up));
+        Ok(())
+    }
+
+    /// Sends a move, and if enabled cross-checks the response with the 
+    /// drone's velocity, warning on any mismatch.
+    async fn confirm_move<F: std::future::Future<Output = Result<()>>>(&self, description: &str, send: F) -> Result<()> {
+        if !self.inner.options.confirm_moves || !self.state_enabled().await {
+            return send.await;
+        }
+
+        let mut seen_moving = false;
+        tokio::pin!(send);
+        let result = loop {
+            tokio::select! {
+                result = &mut send => break result,
+                _ = sleep(Duration::from_millis(50)) => {
+                    if self.latest_state().is_some_and(|s| s.is_moving()) {
+                        seen_moving = true;
+                    }
+                }
+            }
+        };
+
+        let still_moving = self.latest_state().is_some_and(|s| s.is_moving());
+        match &result {
+            Ok(_) if !seen_moving => 
+                warn!("[Tello] WARNING {description} acknowledged, but no movement was seen"),
+            Err(TelloError::Timeout { .. }) if still_moving => 
+                warn!("[Tello] WARNING {description} timed out, but the drone is still moving"),
+            Err(TelloError::Timeout { .. }) if seen_moving => 
+                warn!("[Tello] WARNING {description} timed out, but the drone moved and has stopped so probably completed"),
+            _ => {}
+        }
+
+        result
+    }
+
+    /// Fails with `TelloError::OutOfBounds`, after telling the drone to 
+    /// hover, if the move would take it outside the flight bounds.
+    async fn check_bounds<F: Fn(PositionEstimate) -> PositionEstimate>(&self, f: F) -> Result<()> {
+        if let Some(bounds) = &self.inner.options.bounds {
+            let position = f(*self.inner.position.lock().unwrap());
+            if !position.is_inside(bounds) {
+                warn!("[Tello] WARNING move to ({:.0}, {:.0}, {:.0}) is out of bounds, hovering instead", position.x, position.y, position.z);
+                self.remote_control(0, 0, 0, 0).await?;
+                return Err(TelloError::OutOfBounds);
+            }
+        }
+        Ok(())
+    }
+
+    fn update_position<F: Fn(PositionEstimate) -> PositionEstimate>(&self, f: F) {
+        let mut position = self.inner.position.lock().unwrap();
+        *position = f(*position);
     }
 
     /// Flip left.
@@ -440,7 +1700,7 @@ impl Tello<Connected> {
     /// *nb* fails if battery is low
     /// 
     pub async fn flip_left(&self) -> Result<()> {
-        self.send_expect_ok("flip l").await
+        self.flip("l").await
     }        
 
     /// Flip right.
@@ -448,7 +1708,7 @@ impl Tello<Connected> {
     /// *nb* fails if battery is low 
     ///
     pub async fn flip_right(&self) -> Result<()> {
-        self.send_expect_ok("flip r").await
+        self.flip("r").await
     }        
 
     /// Flip forward.
@@ -456,7 +1716,7 @@ impl Tello<Connected> {
     /// *nb* fails if battery is low 
     ///
     pub async fn flip_forward(&self) -> Result<()> {
-        self.send_expect_ok("flip f").await
+        self.flip("f").await
     }        
 
     /// Flip back.
@@ -464,16 +1724,67 @@ impl Tello<Connected> {
     /// *nb* fails if battery is low 
     ///
     pub async fn flip_back(&self) -> Result<()> {
-        self.send_expect_ok("flip b").await
+        self.flip("b").await
     }        
 
+    async fn flip(&self, direction: &str) -> Result<()> {
+        if self.inner.options.block_flips_when_overheating && self.is_overheating() {
+            return Err(TelloError::Overheating);
+        }
+        self.send_value_expect_ok("flip", direction).await
+    }
+
+    /// Turn on mission pad detection, required before using mission pads at
+    /// all, eg with `set_mission_pad_detection_direction()` or `jump()`.
+    ///
+    /// Detected pads are reported in the state updates, see 
+    /// `TelloState::mission_pad_id`.
+    ///
+    pub async fn enable_mission_pads(&self) -> Result<()> {
+        self.send_expect_ok("mon").await
+    }
+
+    /// Turn off mission pad detection.
+    pub async fn disable_mission_pads(&self) -> Result<()> {
+        self.send_expect_ok("moff").await
+    }
+
+    /// Set which camera(s) look for mission pads.
+    ///
+    /// *nb* Mission pad detection must be enabled first with 
+    /// `enable_mission_pads()`, otherwise the drone refuses, and its 
+    /// response is returned as is in `TelloError::NotOkResponse`.
+    ///
+    /// - `direction` Down, forwards or both
+    ///
+    pub async fn set_mission_pad_detection_direction(&self, direction: MissionPadDirection) -> Result<()> {
+        let response = self.send(&format!("mdirection {}", direction.value())).await?;
+        if response == "ok" {
+            Ok(())
+        }
+        else {
+            Err(TelloError::NotOkResponse { response })
+        }
+    }
+
+    /// Whether the drone is currently overheating (above 90°C), in which 
+    /// case it throttles itself and may refuse to flip or take off.
+    ///
+    /// Always `false` unless state updates were requested when connecting.
+    ///
+    pub fn is_overheating(&self) -> bool {
+        self.inner.overheating.load(Ordering::Relaxed)
+    }
+
     /// Start video as stream of h264-encoded frames.
     ///
     /// Use `TelloOption::with_video()` to set up a channel for receiving the
     /// video frames.
     ///
-    /// *nb* You must consume the frame data! The channel is unlimited and 
-    /// will eventually use up all available memory if you don't.
+    /// *nb* You must consume the frame data! Unless 
+    /// `TelloOptions::video_channel_capacity` is set the channel is 
+    /// unlimited and will eventually use up all available memory if you 
+    /// don't.
     ///
     pub async fn start_video(&self) -> Result<()> {
         self.send_expect_ok("streamon").await
@@ -484,42 +1795,1432 @@ impl Tello<Connected> {
         self.send_expect_ok("streamoff").await
     }
 
-    /// Remote control'
+    /// Set the video stream bitrate.
     ///
-    /// All arguments are -100 to 100 (not sure what units)
+    /// *nb* Needs SDK 3.0 firmware, see `Capability::VideoConfig`. If the 
+    /// drone refuses, its response is returned as the error.
+    ///
+    /// - `bitrate` Automatic, or 1-5 Mbps
+    ///
+    pub async fn set_video_bitrate(&self, bitrate: VideoBitrate) -> Result<()> {
+        self.send_value_expect_ok("setbitrate", bitrate.value()).await
+    }
+
+    /// Set the video stream resolution.
+    ///
+    /// *nb* Needs SDK 3.0 firmware, see `Capability::VideoConfig`. If the 
+    /// drone refuses, its response is returned as the error.
+    ///
+    /// - `resolution` 480p or 720p
+    ///
+    pub async fn set_video_resolution(&self, resolution: VideoResolution) -> Result<()> {
+        self.send_value_expect_ok("setresolution", resolution.value()).await
+    }
+
+    /// Set the video stream frame rate.
+    ///
+    /// *nb* Needs SDK 3.0 firmware, see `Capability::VideoConfig`. If the 
+    /// drone refuses, its response is returned as the error.
+    ///
+    /// - `fps` 5, 15 or 30 frames per second
+    ///
+    pub async fn set_video_fps(&self, fps: VideoFps) -> Result<()> {
+        self.send_value_expect_ok("setfps", fps.value()).await
+    }
+
+    /// Statistics for the received video, eg the actual frame rate.
+    ///
+    /// All zero unless video was requested when connecting.
+    ///
+    pub fn video_stats(&self) -> VideoStats {
+        self.inner.video_stats.lock().unwrap().stats()
+    }
+
+    /// Set the ports the drone sends state updates and video to, eg so 
+    /// several drones can be used from the same host.
+    ///
+    /// Any state or video listeners are restarted on the new ports. The 
+    /// drone keeps sending to them until it restarts, so the options 
+    /// returned by `disconnect_keeping_options()` have 
+    /// `TelloOptions::state_port` and `TelloOptions::video_port` updated to
+    /// match, ready to connect again.
+    ///
+    /// - `state_port` Port for state updates, default 8890
+    /// - `video_port` Port for video, default 11111
+    ///
+    pub async fn set_ports(&self, state_port: u16, video_port: u16) -> Result<()> {
+        self.send_expect_ok(&format!("port {state_port} {video_port}")).await?;
+        *self.inner.ports.lock().unwrap() = (state_port, video_port);
+
+        let mut state_listener = self.inner.state_listener.lock().await;
+        if let Some(listener) = state_listener.take() {
+            let sinks = listener.sinks.clone();
+            listener.stop_listening().await?;
+            *state_listener = Some(StateListener::start_listening(self.inner.options.local_address(state_port), sinks).await?);
+        }
+
+        let mut video_listener = self.inner.video_listener.lock().await;
+        if let Some(listener) = video_listener.take() {
+            let sender = listener.sender.clone();
+            listener.stop_listening().await?;
+            *video_listener = Some(VideoListener::start_listening(self.inner.options.local_address(video_port), sender, self.inner.video_stats.clone(), self.inner.options.video_receive_buffer_size).await?);
+        }
+
+        Ok(())
+    }
+
+    /// Set the name and password of the drone's own WiFi network, eg to 
+    /// tell several drones apart.
+    ///
+    /// *nb* The drone restarts its network after this succeeds, so the 
+    /// connection drops. Disconnect, then wait for the host to join the 
+    /// renamed network and connect again.
+    ///
+    /// - `ssid` The new network name, ASCII with no spaces
+    /// - `password` The new password, ASCII with no spaces
+    ///
+    pub async fn set_wifi_credentials(&self, ssid: &str, password: &str) -> Result<()> {
+        check_credential("SSID", ssid)?;
+        check_credential("password", password)?;
+        self.send_expect_ok(&format!("wifi {ssid} {password}")).await
+    }
+
+    /// Switch the drone to station mode, joining an existing WiFi network 
+    /// instead of running its own, eg to fly several drones from the same
+    /// host.
+    ///
+    /// *nb* The drone restarts after this succeeds, so the connection drops.
+    /// It then has an address assigned by the router rather than 
+    /// 192.168.10.1, which must be found (eg from the router) to connect 
+    /// again. State and video are only received in AP mode.
+    ///
+    /// - `ssid` The network to join, ASCII with no spaces
+    /// - `password` The network password, ASCII with no spaces
+    ///
+    pub async fn join_access_point(&self, ssid: &str, password: &str) -> Result<()> {
+        check_credential("SSID", ssid)?;
+        check_credential("password", password)?;
+        self.send_expect_ok(&format!("ap {ssid} {password}")).await
+    }
+
+    /// Watch the link to the drone, and reconnect if it is lost, as set up
+    /// with `TelloOptions::with_auto_reconnect()`. Run this alongside 
+    /// flying the drone, eg with `tokio::select!`. 
+    ///
+    /// The link is considered lost when no state updates have arrived for 
+    /// the policy's `link_timeout`. Reconnecting opens a new socket for 
+    /// commands, as `reconnect()` does, puts the drone back in command 
+    /// mode, restarts the state and video listeners on the same ports, and
+    /// restarts video if it was being received.
+    ///
+    /// Resolves immediately if auto reconnect was not requested, or to 
+    /// `TelloError::ConnectionLost` on giving up.
+    ///
+    pub async fn supervise(&self) -> Result<()> {
+        let Some(policy) = self.inner.options.auto_reconnect else {
+            return Ok(());
+        };
+
+        let received_at = match self.inner.state_listener.lock().await.as_ref() {
+            Some(listener) => listener.sinks.received_at.clone(),
+            None => return Err(TelloError::StateNotEnabled)
+        };
+
+        let mut watching_since = std::time::Instant::now();
+        loop {
+            sleep(SUPERVISOR_INTERVAL).await;
+
+            let last_heard = received_at.lock().unwrap().map_or(watching_since, |t| t.max(watching_since));
+            if last_heard.elapsed() < policy.link_timeout {
+                continue;
+            }
+
+            warn!("[Tello] WARNING link lost (no state for {:.1}s), reconnecting...", last_heard.elapsed().as_secs_f32());
+            self.reconnect_with(&policy).await?;
+            watching_since = std::time::Instant::now();
+        }
+    }
+
+    async fn reconnect_with(&self, policy: &ReconnectPolicy) -> Result<()> {
+        let mut attempt = 1;
+        while policy.allows_attempt(attempt) {
+            sleep(policy.backoff(attempt)).await;
+
+            let reconnected = async {
+                self.reopen_control_socket().await?;
+                self.send_with_timeout("command", policy.link_timeout).await.and_then(expect_ok)
+            };
+            match reconnected.await {
+                Ok(()) => {
+                    self.restart_listeners().await?;
+                    info!("[Tello] reconnected after {attempt} attempt(s)");
+                    return Ok(());
+                }
+                Err(err) => warn!("[Tello] reconnect attempt #{attempt} failed ({err})")
+            }
+            attempt += 1;
+        }
+
+        Err(TelloError::ConnectionLost { attempts: attempt - 1 })
+    }
+
+    /// Restarts any state and video listeners on the same ports, passing 
+    /// updates on to the same channels.
+    async fn restart_listeners(&self) -> Result<()> {
+        let mut state_listener = self.inner.state_listener.lock().await;
+        if let Some(listener) = state_listener.take() {
+            let (local_address, sinks) = (listener.local_address, listener.sinks.clone());
+            listener.stop_listening().await?;
+            *state_listener = Some(StateListener::start_listening(local_address, sinks).await?);
+        }
+        drop(state_listener);
+
+        let mut video_listener = self.inner.video_listener.lock().await;
+        if let Some(listener) = video_listener.take() {
+            let (local_address, sender) = (listener.local_address, listener.sender.clone());
+            listener.stop_listening().await?;
+            *video_listener = Some(VideoListener::start_listening(local_address, sender, self.inner.video_stats.clone(), self.inner.options.video_receive_buffer_size).await?);
+            drop(video_listener);
+            self.start_video().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remote control'
+    ///
+    /// All arguments are -100 to 100 (not sure what units)
     /// - `left_right` Movement sideways
     /// - `forwards_backwards` Forwards/backwards
     /// - `up_down` Vertical movement
     /// - `yaw` Turn left or right
     ///
     pub async fn remote_control(&self, left_right:i8, forwards_backwards:i8, up_down:i8, yaw:i8) -> Result<()> {
+        let values = (left_right, forwards_backwards, up_down, yaw);
+        *self.inner.remote_control.values.lock().unwrap() = values;
+        self.inner.remote_control.active.store(values != (0, 0, 0, 0), Ordering::Relaxed);
         self.send_expect_nothing(&format!("rc {left_right} {forwards_backwards} {up_down} {yaw}")).await
     }
 
+    /// Set how often the latest remote control values are repeated, when 
+    /// `TelloOptions::repeat_remote_control` is set. Default 20 times a 
+    /// second.
+    ///
+    /// - `per_second` Repeats per second, 1 to 100
+    ///
+    pub async fn set_remote_control_repeat_rate(&self, per_second: u32) -> Result<()> {
+        if !(1..=100).contains(&per_second) {
+            return Err(TelloError::OutOfRange);
+        }
+        if let Some(repeater) = self.inner.remote_control_repeater.lock().await.as_ref() {
+            repeater.set_interval(Duration::from_secs(1) / per_second);
+        }
+        Ok(())
+    }
+
+    /// Hold the given remote control values for a length of time, then stop,
+    /// eg to strafe right for 2 seconds.
+    ///
+    /// The values are sent repeatedly so the drone doesn't drift back to 
+    /// neutral early.
+    ///
+    /// All arguments as `remote_control()`, plus
+    /// - `duration` How long to hold the values for
+    ///
+    pub async fn remote_control_for(&self, left_right:i8, forwards_backwards:i8, up_down:i8, yaw:i8, duration:Duration) -> Result<()> {
+        let start = tokio::time::Instant::now();
+        while start.elapsed() < duration {
+            self.remote_control(left_right, forwards_backwards, up_down, yaw).await?;
+            sleep(REMOTE_CONTROL_INTERVAL.min(duration.saturating_sub(start.elapsed()))).await;
+        }
+
+        self.remote_control(0, 0, 0, 0).await
+    }
+
+    /// Smoothly change the remote control values from their current values 
+    /// to the given ones, for less jerky movement.
+    ///
+    /// All arguments as `remote_control()`, plus
+    /// - `over` How long to take to reach the new values
+    ///
+    pub async fn ramp_remote_control(&self, left_right:i8, forwards_backwards:i8, up_down:i8, yaw:i8, over:Duration) -> Result<()> {
+        let from = *self.inner.remote_control.values.lock().unwrap();
+        let steps = (over.as_millis() / REMOTE_CONTROL_INTERVAL.as_millis()).max(1) as i32;
+
+        let interpolate = |from:i8, to:i8, step:i32| -> i8 {
+            (from as i32 + (to as i32 - from as i32) * step / steps) as i8
+        };
+
+        for step in 1..=steps {
+            self.remote_control(
+                interpolate(from.0, left_right, step), 
+                interpolate(from.1, forwards_backwards, step), 
+                interpolate(from.2, up_down, step), 
+                interpolate(from.3, yaw, step)
+            ).await?;
+
+            if step < steps {
+                sleep(REMOTE_CONTROL_INTERVAL).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Smoothly change the forwards/backwards remote control value, leaving 
+    /// the others as they are.
+    ///
+    /// - `target` The new forwards/backwards value, -100 to 100
+    /// - `over` How long to take to reach it
+    ///
+    pub async fn ramp_forward(&self, target:i8, over:Duration) -> Result<()> {
+        let (left_right, _, up_down, yaw) = *self.inner.remote_control.values.lock().unwrap();
+        self.ramp_remote_control(left_right, target, up_down, yaw, over).await
+    }
+
 
     //////////////////////////////////////////////////////////////////////////
 
+    /// Handle commands from the channel set up with 
+    /// `TelloOptions::with_command()`, until the channel is closed or, if 
+    /// set up with `TelloOptions::with_command_handle()`, shutdown is 
+    /// requested.
+    ///
+    /// `TelloCommand::EmergencyStop`, if armed with 
+    /// `TelloCommand::ArmEmergencyStop`, is handled as soon as it arrives, 
+    /// even while another command is waiting for its response. That command
+    /// is abandoned, and any still queued are dropped. Arming also happens 
+    /// as soon as the command arrives.
+    ///
+    /// On shutdown the keep alive and remote control repeat tasks are also
+    /// stopped, so nothing more is sent to the drone.
     pub async fn handle_commands(&self) -> Result<()> {
-        if let Some(command_receiver) = &self.inner.command_receiver { 
+        let result = self.run_command_loop().await;
+
+        if let Some(command_control) = &self.inner.options.command_control {
+            if command_control.is_shutdown_requested() {
+                self.stop_background_tasks().await;
+            }
+            command_control.set_finished();
+        }
+
+        result
+    }
+
+    /// Adds a command to those waiting to be handled, replacing a remote 
+    /// control command at the back if coalescing them. Arming an emergency
+    /// stop isn't queued but done straight away, and an emergency stop that
+    /// isn't armed is dropped.
+    fn queue_command(&self, pending: &mut VecDeque<TelloCommand>, command: TelloCommand) -> Result<()> {
+        match command {
+            TelloCommand::ArmEmergencyStop => {
+                self.arm_emergency_stop();
+                return self.report_command_result(command, Ok(()));
+            }
+            TelloCommand::EmergencyStop if !self.is_emergency_stop_armed() => {
+                // not worth stopping command handling for
+                warn!("[Tello] WARNING emergency stop not armed, ignored");
+                if let Some(result_tx) = &self.inner.options.command_result_sender {
+                    let _ = result_tx.send(TelloCommandResult { command, result: Err(emergency_stop_not_armed()) });
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if self.inner.options.coalesce_remote_control 
+            && matches!(command, TelloCommand::RemoteControl { .. }) 
+            && matches!(pending.back(), Some(TelloCommand::RemoteControl { .. })) {
+            pending.pop_back();
+        }
+        pending.push_back(command);
+        Ok(())
+    }
+
+    async fn run_command_loop(&self) -> Result<()> {
+        if let Some(command_receiver) = &self.inner.options.command_receiver { 
             let mut command_rx = command_receiver.lock().await;
-            while let Some(command) = command_rx.recv().await {
-                match command {
-                    TelloCommand::TakeOff => self.take_off().await?,
-                    TelloCommand::Land => self.land().await?,
-                    TelloCommand::StopAndHover => self.stop().await?,
-                    TelloCommand::EmergencyStop => self.emergency_stop().await?,
-                    TelloCommand::RemoteControl { left_right, forwards_backwards, up_down, yaw } => 
-                        self.remote_control(left_right, forwards_backwards, up_down, yaw).await?,
-                    TelloCommand::FlipLeft => self.flip_left().await?,
-                    TelloCommand::FlipRight => self.flip_right().await?,
-                    TelloCommand::FlipForward => self.flip_forward().await?,
-                    TelloCommand::FlipBack => self.flip_back().await?
-                 }
+            let mut pending = VecDeque::new();
+            loop {
+                if let Some(command_control) = &self.inner.options.command_control {
+                    if command_control.is_shutdown_requested() {
+                        info!("[Tello] command handling shut down");
+                        break;
+                    }
+                }
+
+                if pending.is_empty() {
+                    let command = match &self.inner.options.command_control {
+                        Some(command_control) => {
+                            tokio::select! {
+                                _ = command_control.shutdown_requested() => {
+                                    info!("[Tello] command handling shut down");
+                                    break;
+                                }
+                                command = command_rx.recv() => command
+                            }
+                        }
+                        None => command_rx.recv().await
+                    };
+
+                    let Some(command) = command else { break };
+                    self.queue_command(&mut pending, command)?;
+                }
+
+                // an emergency stop jumps the queue, and everything else 
+                // waiting is dropped
+                while let Ok(command) = command_rx.try_recv() {
+                    self.queue_command(&mut pending, command)?;
+                }
+                if pending.iter().any(|c| matches!(c, TelloCommand::EmergencyStop)) {
+                    warn!("[Tello] EMERGENCY STOP, dropping {} queued commands", pending.len() - 1);
+                    pending.clear();
+                    let result = self.emergency_stop().await;
+                    self.report_command_result(TelloCommand::EmergencyStop, result)?;
+                    continue;
+                }
+
+                if let Some(command) = pending.pop_front() {
+                    // keep receiving while the command is handled, so an 
+                    // emergency stop doesn't have to wait for it to finish
+                    let handling = self.handle_command(command.clone());
+                    tokio::pin!(handling);
+                    let result = loop {
+                        tokio::select! {
+                            result = &mut handling => break Some(result),
+                            received = command_rx.recv() => match received {
+                                Some(TelloCommand::EmergencyStop) if self.is_emergency_stop_armed() => break None,
+                                Some(command) => self.queue_command(&mut pending, command)?,
+                                None => break Some((&mut handling).await)
+                            }
+                        }
+                    };
+
+                    match result {
+                        Some(result) => self.report_command_result(command, result)?,
+                        None => {
+                            warn!("[Tello] EMERGENCY STOP, abandoning {command:?} and dropping {} queued commands", pending.len());
+                            pending.clear();
+                            let result = self.emergency_stop().await;
+                            if let Some(result_tx) = &self.inner.options.command_result_sender {
+                                let abandoned = Err(TelloError::Generic { msg: "abandoned for an emergency stop".to_string() });
+                                let _ = result_tx.send(TelloCommandResult { command, result: abandoned });
+                            }
+                            self.report_command_result(TelloCommand::EmergencyStop, result)?;
+                        }
+                    }
+                }
+            }
+        }
+    
+        Ok(())
+
+    }
+
+    /// Passes the outcome of a command back if results were requested, 
+    /// otherwise any error stops command handling.
+    fn report_command_result(&self, command: TelloCommand, result: Result<()>) -> Result<()> {
+        match &self.inner.options.command_result_sender {
+            Some(result_tx) => {
+                let _ = result_tx.send(TelloCommandResult { command, result });
+                Ok(())
+            }
+            None => result
+        }
+    }
+
+    async fn handle_command(&self, command: TelloCommand) -> Result<()> {
+        match command {
+            TelloCommand::TakeOff => self.take_off().await,
+            TelloCommand::Land => self.land().await,
+            TelloCommand::StopAndHover => self.stop().await,
+            TelloCommand::EmergencyStop => self.emergency_stop().await,
+            TelloCommand::ArmEmergencyStop => {
+                self.arm_emergency_stop();
+                Ok(())
+            }
+            TelloCommand::RemoteControl { left_right, forwards_backwards, up_down, yaw } => 
+                self.remote_control(left_right, forwards_backwards, up_down, yaw).await,
+            TelloCommand::FlipLeft => self.flip_left().await,
+            TelloCommand::FlipRight => self.flip_right().await,
+            TelloCommand::FlipForward => self.flip_forward().await,
+            TelloCommand::FlipBack => self.flip_back().await,
+            TelloCommand::MoveUp(distance) => self.move_up(distance).await,
+            TelloCommand::MoveDown(distance) => self.move_down(distance).await,
+            TelloCommand::MoveForward(distance) => self.move_forward(distance).await,
+            TelloCommand::MoveBack(distance) => self.move_back(distance).await,
+            TelloCommand::MoveLeft(distance) => self.move_left(distance).await,
+            TelloCommand::MoveRight(distance) => self.move_right(distance).await,
+            TelloCommand::TurnClockwise(degrees) => self.turn_clockwise(degrees).await,
+            TelloCommand::TurnCounterClockwise(degrees) => self.turn_counterclockwise(degrees).await,
+            TelloCommand::SetSpeed(speed) => self.set_speed(speed).await
+        }
+    }
+
+}
+
+/// Binds the local socket for commands and connects it to the drone, 
+/// retrying until both succeed or binding has failed repeatedly.
+async fn open_control_socket(options: &TelloOptions) -> Result<UdpSocket> {
+    let local_address = options.local_address(options.local_control_port);
+    let drone_address = format!("{}:{}", options.drone_host, options.control_port);
+
+    info!("[Tello] CONNECT {local_address} → {drone_address}");
+    options.emit(TelloEvent::Connecting);
+
+    // bind local socket
+    info!("[Tello] binding local {local_address}...");
+    let mut i = 0;
+    let sock = loop {
+        i += 1;
+        match UdpSocket::bind(&local_address).await {
+            Ok(sock) => break sock,
+            Err(err) if i < BIND_ATTEMPTS => {
+                warn!("[Tello] bind attempt #{i} failed ({err}), retrying...");
+                options.emit(TelloEvent::ConnectRetry { attempt: i });
+                sleep(Duration::from_millis(100)).await;
             }
+            Err(err) => return Err(err.into())
         }
+    };
     
+    // connect to drone
+    info!("[Tello] connecting to drone at {drone_address}...");
+    let mut i = 0;
+    loop {
+        i += 1;
+        match sock.connect(&drone_address).await {
+            Ok(_) => {
+                info!("[Tello] CONNECTED");
+                return Ok(sock);
+            }
+            Err(err) => {
+                warn!("[Tello] connection attempt #{i} failed ({err}), retrying...");
+                options.emit(TelloEvent::ConnectRetry { attempt: i });
+                sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+/// Parses an attitude as returned by `attitude?`, eg 
+/// "pitch:-2;roll:1;yaw:45;" to `(pitch, roll, yaw)`.
+fn parse_attitude(response: &str) -> Result<(i16, i16, i16)> {
+    let parse_error = || TelloError::ParseError { msg: format!("unexpected response: \"{response}\"") };
+
+    let (mut pitch, mut roll, mut yaw) = (None, None, None);
+    for field in response.trim().split(';').filter(|f| !f.is_empty()) {
+        let (key, value) = field.split_once(':').ok_or_else(parse_error)?;
+        let value = value.trim().parse::<i16>().map_err(|_| parse_error())?;
+        match key.trim() {
+            "pitch" => pitch = Some(value),
+            "roll" => roll = Some(value),
+            "yaw" => yaw = Some(value),
+            _ => {}
+        }
+    }
+
+    match (pitch, roll, yaw) {
+        (Some(pitch), Some(roll), Some(yaw)) => Ok((pitch, roll, yaw)),
+        _ => Err(parse_error())
+    }
+}
+
+/// Parses a temperature range as returned by `temp?`, eg "20~25C"
+fn parse_temperature(response: &str) -> Result<(i16, i16)> {
+    let range = response.trim().trim_end_matches('C');
+    match range.split_once('~') {
+        Some((low, high)) => Ok((parse_value(low, response)?, parse_value(high, response)?)),
+        None => {
+            // just the one temperature
+            let t = parse_value(range, response)?;
+            Ok((t, t))
+        }
+    }
+}
+
+/// Parses a distance with units, eg "10cm", "100mm" or "3dm", to cm.
+fn parse_distance<T: TryFrom<i32>>(response: &str) -> Result<T> {
+    let r = response.trim();
+    let cm = if let Some(mm) = r.strip_suffix("mm") {
+        parse_value::<i32>(mm, response)? / 10
+    }
+    else if let Some(cm) = r.strip_suffix("cm") {
+        parse_value::<i32>(cm, response)?
+    }
+    else if let Some(dm) = r.strip_suffix("dm") {
+        parse_value::<i32>(dm, response)? * 10
+    }
+    else {
+        parse_value::<i32>(r, response)?
+    };
+    T::try_from(cm).map_err(|_| TelloError::ParseError { msg: format!("unexpected response: \"{response}\"") })
+}
+
+/// Parses a value from (part of) a response.
+fn parse_value<T: std::str::FromStr>(value: &str, response: &str) -> Result<T> {
+    value.trim().parse::<T>().map_err(|_| TelloError::ParseError { msg: format!("unexpected response: \"{response}\"") })
+}
+
+/// The turn from one yaw to another by the shortest way round, in degrees, 
+/// positive for clockwise, -179 to 180.
+fn shortest_turn(from: i16, to: i16) -> i16 {
+    let turn = (to as i32 - from as i32).rem_euclid(360);
+    (if turn > 180 { turn - 360 } else { turn }) as i16
+}
+
+/// Checks coordinates are within reach, ie -500 to 500 cm.
+fn check_coordinates(coordinates: &[i16]) -> Result<()> {
+    if coordinates.iter().all(|c| (-MAX_COORDINATE..=MAX_COORDINATE).contains(c)) {
         Ok(())
+    }
+    else {
+        Err(TelloError::OutOfRange)
+    }
+}
 
+/// Checks a point is far enough away to move to, as the drone rejects 
+/// moves of less than 20cm.
+fn check_not_too_close(x: i16, y: i16, z: i16) -> Result<()> {
+    let too_close = |c: i16| c.unsigned_abs() < MIN_MOVE_DISTANCE;
+    if too_close(x) && too_close(y) && too_close(z) {
+        Err(TelloError::OutOfRange)
+    }
+    else {
+        Ok(())
     }
+}
+
+/// Whether two points are in a straight line with the origin, so no arc 
+/// passes through all three.
+fn in_line((x1, y1, z1): (i16, i16, i16), (x2, y2, z2): (i16, i16, i16)) -> bool {
+    let (x1, y1, z1, x2, y2, z2) = (x1 as i32, y1 as i32, z1 as i32, x2 as i32, y2 as i32, z2 as i32);
+    y1 * z2 == z1 * y2 && z1 * x2 == x1 * z2 && x1 * y2 == y1 * x2
+}
+
+/// Formats a `curve` command, eg `curve 20 20 0 40 60 0 30`
+fn curve_command((x1, y1, z1): (i16, i16, i16), (x2, y2, z2): (i16, i16, i16), speed: u8) -> String {
+    format!("curve {x1} {y1} {z1} {x2} {y2} {z2} {speed}")
+}
 
-}
\ No newline at end of file
+/// Formats a `jump` command, eg `jump 0 0 100 50 90 m1 m2`
+fn jump_command((x, y, z): (i16, i16, i16), speed: u8, yaw: i16, (mid1, mid2): (u8, u8)) -> String {
+    format!("jump {x} {y} {z} {speed} {yaw} m{mid1} m{mid2}")
+}
+
+/// Checks a WiFi network name or password can be sent in a 
+/// space-separated command.
+fn check_credential(what: &str, value: &str) -> Result<()> {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_graphic()) {
+        Err(TelloError::Generic { msg: format!("WiFi {what} must be ASCII with no spaces") })
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// Checks a value is one the drone accepts, so it fails immediately rather
+/// than waiting for the drone to respond with an error.
+fn check_range<T: PartialOrd>(value: T, range: std::ops::RangeInclusive<T>) -> Result<()> {
+    if range.contains(&value) {
+        Ok(())
+    }
+    else {
+        Err(TelloError::OutOfRange)
+    }
+}
+
+/// When the command log started, so that connecting again carries on 
+/// after the last command logged.
+fn command_log_started(options: &TelloOptions) -> std::time::Instant {
+    let logged = options.command_log.as_ref()
+        .and_then(|log| log.lock().unwrap().last().map(|(sent, _)| *sent))
+        .unwrap_or_default();
+    let now = std::time::Instant::now();
+    now.checked_sub(logged).unwrap_or(now)
+}
+
+/// The error for a failure to send, `TelloError::WiFiLost` if the network 
+/// has gone.
+fn send_error(err: std::io::Error) -> TelloError {
+    use std::io::ErrorKind;
+    match err.kind() {
+        ErrorKind::NetworkUnreachable | ErrorKind::NetworkDown | ErrorKind::HostUnreachable => TelloError::WiFiLost,
+        _ => err.into()
+    }
+}
+
+fn emergency_stop_not_armed() -> TelloError {
+    TelloError::Generic { msg: "emergency stop not armed, call arm_emergency_stop() first".to_string() }
+}
+
+fn expect_ok(response: String) -> Result<()> {
+    if response == "ok" {
+        Ok(())
+    }
+    else {
+        Err(TelloError::from_not_ok_response(response))
+    }
+}
+
+/// Whether the response to an `EXT` command acknowledges it, ie "ok" or 
+/// the part of the board it's for then "ok", eg "led ok" for `led 255 0 0`.
+fn is_ext_ack(subcommand: &str, response: &str) -> bool {
+    let part = match subcommand.split_whitespace().next() {
+        Some("mled") => "matrix",
+        Some(keyword) => keyword,
+        None => return false
+    };
+    response == "ok" || response.strip_suffix(" ok") == Some(part)
+}
+
+/// Whether the command sets WiFi credentials, ie `wifi` or `ap`.
+fn is_credential_command(command: &str) -> bool {
+    matches!(command.split(' ').next(), Some("wifi" | "ap"))
+}
+
+/// The command as it should appear in logs and errors, with any WiFi 
+/// password masked, eg `wifi mynet ****`.
+fn redacted(command: &str) -> Cow<'_, str> {
+    let parts: Vec<&str> = command.split(' ').collect();
+    match parts.as_slice() {
+        [keyword @ ("wifi" | "ap"), ssid, _password, ..] => Cow::Owned(format!("{keyword} {ssid} ****")),
+        _ => Cow::Borrowed(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    /// A stand-in for the drone on the loopback interface, answering each 
+    /// command with the responses `respond` gives for it (none, one, or 
+    /// several datagrams) and passing on the commands received.
+    struct MockTello {
+        port: u16,
+        commands: mpsc::UnboundedReceiver<String>,
+        task: tokio::task::JoinHandle<()>
+    }
+
+    impl MockTello {
+        async fn start<F>(respond: F) -> Self 
+        where F: Fn(&str) -> Vec<String> + Send + 'static {
+            let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let port = sock.local_addr().unwrap().port();
+            let (tx, commands) = mpsc::unbounded_channel();
+
+            let task = tokio::spawn(async move {
+                let mut buf = [0; 1024];
+                while let Ok((n, from)) = sock.recv_from(&mut buf).await {
+                    let command = String::from_utf8_lossy(&buf[..n]).into_owned();
+                    for response in respond(&command) {
+                        sock.send_to(response.as_bytes(), from).await.unwrap();
+                    }
+                    let _ = tx.send(command);
+                }
+            });
+
+            Self { port, commands, task }
+        }
+
+        /// Options to connect to this drone, from any free local port.
+        fn options(&self) -> TelloOptions {
+            TelloOptions {
+                drone_host: "127.0.0.1".to_string(),
+                control_port: self.port,
+                local_control_port: 0,
+                local_interface: Some([127, 0, 0, 1].into()),
+                ..TelloOptions::default()
+            }
+        }
+
+        async fn connect(&mut self) -> Tello<Connected> {
+            self.connect_with(self.options()).await
+        }
+
+        /// Connects, skipping the commands sent while connecting.
+        async fn connect_with(&mut self, options: TelloOptions) -> Tello<Connected> {
+            let drone = Tello::new().assume_wifi().await.unwrap().connect_with(options).await.unwrap();
+            while self.commands.try_recv().is_ok() {}
+            drone
+        }
+
+        /// The next command received, failing if none arrives soon.
+        async fn next_command(&mut self) -> String {
+            tokio::time::timeout(Duration::from_secs(1), self.commands.recv()).await
+                .expect("no command received")
+                .unwrap()
+        }
+
+        /// Whether no more commands have been received.
+        fn received_nothing_more(&mut self) -> bool {
+            self.commands.try_recv().is_err()
+        }
+    }
+
+    impl Drop for MockTello {
+        fn drop(&mut self) {
+            self.task.abort();
+        }
+    }
+
+    /// Responds as a drone on the ground would to the commands sent when 
+    /// connecting, with a newline ending a query's response as the drone 
+    /// does, and "ok" to anything else.
+    fn ok(command: &str) -> Vec<String> {
+        match command {
+            "battery?" => vec!["87\r\n".to_string()],
+            _ => vec!["ok".to_string()]
+        }
+    }
+
+    /// As `ok()`, but with the given responses to particular commands.
+    fn responding(responses: &'static [(&'static str, &'static [&'static str])]) -> impl Fn(&str) -> Vec<String> + Send + 'static {
+        move |command| match responses.iter().find(|(c, _)| *c == command) {
+            Some((_, r)) => r.iter().map(|r| r.to_string()).collect(),
+            None => ok(command)
+        }
+    }
+
+    #[tokio::test]
+    async fn connecting_sends_command_then_checks_battery() {
+        let mut mock = MockTello::start(ok).await;
+        let options = mock.options();
+        let _drone = Tello::new().assume_wifi().await.unwrap().connect_with(options).await.unwrap();
+
+        assert_eq!(mock.next_command().await, "command");
+        assert_eq!(mock.next_command().await, "battery?");
+    }
+
+    #[tokio::test]
+    async fn send_expect_ok_accepts_ok() {
+        let mut mock = MockTello::start(ok).await;
+        let drone = mock.connect().await;
+
+        drone.send_expect_ok("takeoff").await.unwrap();
+        assert_eq!(mock.next_command().await, "takeoff");
+    }
+
+    #[tokio::test]
+    async fn send_expect_parses_the_response() {
+        let mut mock = MockTello::start(responding(&[("speed?", &["fast"])])).await;
+        let drone = mock.connect().await;
+
+        assert_eq!(drone.send_expect::<u8>("battery?").await.unwrap(), 87);
+        assert_eq!(mock.next_command().await, "battery?");
+
+        assert!(matches!(drone.send_expect::<u8>("speed?").await, Err(TelloError::ParseError { .. })));
+    }
+
+    #[tokio::test]
+    async fn forced_stop_is_skipped_for_the_real_response() {
+        let mut mock = MockTello::start(responding(&[("stop", &["forced stop", "ok"])])).await;
+        let drone = mock.connect().await;
+
+        drone.send_expect_ok("stop").await.unwrap();
+        assert_eq!(mock.next_command().await, "stop");
+        assert!(mock.received_nothing_more());
+    }
+
+    #[tokio::test]
+    async fn error_responses_are_mapped() {
+        let mut mock = MockTello::start(responding(&[
+            ("flip l", &["error Motor stop"]),
+            ("up 600", &["out of range"]),
+            ("land", &["error"]),
+            ("cw 90", &["something else"])
+        ])).await;
+        let drone = mock.connect().await;
+
+        assert!(matches!(drone.send_expect_ok("flip l").await, Err(TelloError::MotorStop)));
+        assert!(matches!(drone.send_expect_ok("up 600").await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.send_expect_ok("land").await, Err(TelloError::NonSpecificError)));
+        assert!(matches!(drone.send_expect_ok("cw 90").await, Err(TelloError::NotOkResponse { response }) if response == "something else"));
+    }
+
+    #[tokio::test]
+    async fn no_response_times_out() {
+        let mut mock = MockTello::start(responding(&[("emergency", &[])])).await;
+        let drone = mock.connect().await;
+
+        let result = drone.send_with_timeout("emergency", Duration::from_millis(100)).await;
+        assert!(matches!(result, Err(TelloError::Timeout { command }) if command == "emergency"));
+    }
+
+    #[tokio::test]
+    async fn emergency_stop_jumps_queued_commands() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        let tx = options.with_command();
+        let drone = mock.connect_with(options).await;
+
+        tx.send(TelloCommand::TakeOff).unwrap();
+        for yaw in 0..5 {
+            tx.send(TelloCommand::RemoteControl { left_right: 0, forwards_backwards: 0, up_down: 0, yaw }).unwrap();
+        }
+        tx.send(TelloCommand::ArmEmergencyStop).unwrap();
+        tx.send(TelloCommand::EmergencyStop).unwrap();
+        drop(tx);
+
+        drone.handle_commands().await.unwrap();
+        assert_eq!(mock.next_command().await, "emergency");
+        sleep(Duration::from_millis(50)).await;
+        assert!(mock.received_nothing_more());
+    }
+
+    #[tokio::test]
+    async fn emergency_stop_interrupts_the_command_in_progress() {
+        // the move is never acknowledged
+        let mut mock = MockTello::start(responding(&[("forward 100", &[])])).await;
+        let mut options = mock.options();
+        let (tx, mut results) = options.with_command_results();
+        let drone = mock.connect_with(options).await;
+
+        tx.send(TelloCommand::MoveForward(100)).unwrap();
+        let interrupt = async {
+            assert_eq!(mock.next_command().await, "forward 100");
+            for yaw in 0..5 {
+                tx.send(TelloCommand::RemoteControl { left_right: 0, forwards_backwards: 0, up_down: 0, yaw }).unwrap();
+            }
+            tx.send(TelloCommand::ArmEmergencyStop).unwrap();
+            tx.send(TelloCommand::EmergencyStop).unwrap();
+            drop(tx);
+            assert_eq!(mock.next_command().await, "emergency");
+        };
+
+        let (handled, _) = tokio::time::timeout(Duration::from_secs(2), async {
+            tokio::join!(drone.handle_commands(), interrupt)
+        }).await.expect("emergency stop waited for the move");
+        handled.unwrap();
+
+        let armed = results.recv().await.unwrap();
+        assert_eq!(armed.command, TelloCommand::ArmEmergencyStop);
+        let abandoned = results.recv().await.unwrap();
+        assert_eq!(abandoned.command, TelloCommand::MoveForward(100));
+        assert!(abandoned.result.is_err());
+        let stopped = results.recv().await.unwrap();
+        assert_eq!(stopped.command, TelloCommand::EmergencyStop);
+        assert!(stopped.result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn emergency_stop_is_ignored_unless_armed() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        let tx = options.with_command();
+        let drone = mock.connect_with(options).await;
+
+        tx.send(TelloCommand::EmergencyStop).unwrap();
+        tx.send(TelloCommand::TakeOff).unwrap();
+        drop(tx);
+
+        drone.handle_commands().await.unwrap();
+        assert_eq!(mock.next_command().await, "takeoff");
+        sleep(Duration::from_millis(50)).await;
+        assert!(mock.received_nothing_more());
+    }
+
+    #[tokio::test]
+    async fn timeouts_after_leaving_the_wifi_are_recoverable() {
+        let mut mock = MockTello::start(responding(&[("land", &[])])).await;
+        let drone = mock.connect().await;
+
+        drone.inner.wifi_lost.store(true, Ordering::Relaxed);
+        let result = drone.send_with_timeout("land", Duration::from_millis(100)).await;
+        assert!(matches!(result, Err(TelloError::WiFiLost)));
+        assert!(result.unwrap_err().is_recoverable());
+    }
+
+    #[tokio::test]
+    async fn out_of_range_moves_are_rejected_without_sending() {
+        let mut mock = MockTello::start(ok).await;
+        let drone = mock.connect().await;
+
+        assert!(matches!(drone.move_up(10).await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.move_forward(501).await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.turn_clockwise(400).await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.turn_counterclockwise(0).await, Err(TelloError::OutOfRange)));
+        sleep(Duration::from_millis(50)).await;
+        assert!(mock.received_nothing_more());
+    }
+
+    #[tokio::test]
+    async fn small_moves_follow_the_policy() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.small_moves = SmallMovePolicy::ClampUp;
+        let drone = mock.connect_with(options).await;
+
+        drone.move_up(10).await.unwrap();
+        assert_eq!(mock.next_command().await, "up 20");
+
+        let mut options = mock.options();
+        options.small_moves = SmallMovePolicy::Ignore;
+        let drone = mock.connect_with(options).await;
+
+        drone.move_up(10).await.unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert!(mock.received_nothing_more());
+    }
+
+    #[test]
+    fn ext_acks() {
+        assert!(is_ext_ack("led 255 0 0", "ok"));
+        assert!(is_ext_ack("led 255 0 0", "led ok"));
+        assert!(is_ext_ack("led br 1 255 0 0", "led ok"));
+        assert!(is_ext_ack("mled l r 1 hello", "matrix ok"));
+
+        assert!(!is_ext_ack("led 255 0 0", "error ok"));
+        assert!(!is_ext_ack("led 255 0 0", "matrix ok"));
+        assert!(!is_ext_ack("led 255 0 0", "led error"));
+        assert!(!is_ext_ack("led 255 0 0", "ok led"));
+        assert!(!is_ext_ack("led 255 0 0", "error"));
+        assert!(!is_ext_ack("", "ok"));
+    }
+
+    #[tokio::test]
+    async fn ext_commands_are_prefixed_and_acknowledged() {
+        let mut mock = MockTello::start(responding(&[
+            ("EXT led 255 0 0", &["led ok"]),
+            ("EXT mled l r 1 hi", &["matrix ok"]),
+            ("EXT tof?", &["tof 512"])
+        ])).await;
+        let drone = mock.connect().await;
+
+        drone.set_led_rgb(255, 0, 0).await.unwrap();
+        assert_eq!(mock.next_command().await, "EXT led 255 0 0");
+        drone.display_matrix_string('r', 'l', "hi").await.unwrap();
+        assert_eq!(mock.next_command().await, "EXT mled l r 1 hi");
+        assert_eq!(drone.ext_query("tof?").await.unwrap(), "tof 512");
+        assert!(drone.ext("tof?").await.is_err());
+        assert!(drone.ext_send("led 255 0 0", "matrix ok").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn command_log_masks_passwords_and_replay_skips_them() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        let log = options.with_command_log();
+        let drone = mock.connect_with(options).await;
+
+        drone.send_expect_ok("ap mynet secret123").await.unwrap();
+        drone.send_expect_ok("takeoff").await.unwrap();
+        mock.next_command().await;
+        mock.next_command().await;
+
+        let logged: Vec<(Duration, String)> = log.lock().unwrap().clone();
+        assert!(logged.iter().all(|(_, command)| !command.contains("secret123")));
+        assert!(logged.iter().any(|(_, command)| command == "ap mynet ****"));
+
+        let credentials = logged.iter().filter(|(_, c)| c.starts_with("ap ")).cloned().collect::<Vec<_>>();
+        drone.replay_log(&credentials).await.unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert!(mock.received_nothing_more());
+    }
+
+    /// A local port that was free a moment ago.
+    async fn free_local_port() -> u16 {
+        UdpSocket::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port()
+    }
+
+    #[tokio::test]
+    async fn reconnect_rebinds_the_same_local_port() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.local_control_port = free_local_port().await;
+        options.keep_alive = true;
+        let drone = mock.connect_with(options).await;
+
+        drone.reconnect().await.unwrap();
+        assert_eq!(mock.next_command().await, "command");
+        assert_eq!(mock.next_command().await, "battery?");
+        assert!(drone.inner.keep_alive.lock().await.is_some());
+
+        drone.send_expect_ok("takeoff").await.unwrap();
+        assert_eq!(mock.next_command().await, "takeoff");
+    }
+
+    #[tokio::test]
+    async fn auto_reconnect_rebinds_the_same_local_port() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.local_control_port = free_local_port().await;
+        options.repeat_remote_control = true;
+        let drone = mock.connect_with(options).await;
+
+        let policy = ReconnectPolicy { initial_backoff: Duration::ZERO, ..ReconnectPolicy::default() };
+        drone.reconnect_with(&policy).await.unwrap();
+        assert_eq!(mock.next_command().await, "command");
+        assert!(drone.inner.remote_control_repeater.lock().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn split_responses_are_joined() {
+        let mut mock = MockTello::start(|command: &str| match command {
+            // a newline ends the response
+            "sn?" => vec!["A".repeat(200), format!("{}\r\n", "B".repeat(198))],
+            // otherwise it's over when no more arrives
+            "sdk?" => vec!["C".repeat(200), "D".repeat(200)],
+            _ => ok(command)
+        }).await;
+        let drone = mock.connect().await;
+
+        assert_eq!(drone.serial_number().await.unwrap(), format!("{}{}", "A".repeat(200), "B".repeat(198)));
+        assert_eq!(drone.sdk_version().await.unwrap(), format!("{}{}", "C".repeat(200), "D".repeat(200)));
+        drone.send_expect_ok("takeoff").await.unwrap();
+    }
+
+    #[test]
+    fn shortest_turns() {
+        // positive is clockwise, crossing ±180 if that is shorter
+        assert_eq!(shortest_turn(170, -170), 20);
+        assert_eq!(shortest_turn(-170, 170), -20);
+        assert_eq!(shortest_turn(0, 90), 90);
+        assert_eq!(shortest_turn(90, 0), -90);
+
+        // half way round either way, always the same way
+        assert_eq!(shortest_turn(0, 180), 180);
+        assert_eq!(shortest_turn(180, 0), 180);
+        assert_eq!(shortest_turn(-90, 90), 180);
+
+        assert_eq!(shortest_turn(0, 0), 0);
+        assert_eq!(shortest_turn(-135, -135), 0);
+        assert_eq!(shortest_turn(180, -180), 0);
+    }
+
+    #[test]
+    fn attitudes() {
+        assert_eq!(parse_attitude("pitch:1;roll:2;yaw:3;").unwrap(), (1, 2, 3));
+        assert_eq!(parse_attitude(" pitch:-2;roll:0;yaw:-179;\r\n").unwrap(), (-2, 0, -179));
+        assert_eq!(parse_attitude("yaw:45;roll:1;pitch:-2").unwrap(), (-2, 1, 45));
+
+        assert!(matches!(parse_attitude("pitch:1;roll:2;"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_attitude("pitch:1;roll:x;yaw:3;"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_attitude("pitch:1;roll:2;yaw:40000;"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_attitude("error"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_attitude(""), Err(TelloError::ParseError { .. })));
+    }
+
+    #[test]
+    fn temperatures() {
+        assert_eq!(parse_temperature("60~63C").unwrap(), (60, 63));
+        assert_eq!(parse_temperature("20~25C\r\n").unwrap(), (20, 25));
+        assert_eq!(parse_temperature("70C").unwrap(), (70, 70));
+
+        assert!(matches!(parse_temperature("60~C"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_temperature("60~99999C"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_temperature("error"), Err(TelloError::ParseError { .. })));
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(parse_distance::<u16>("100mm").unwrap(), 10);
+        assert_eq!(parse_distance::<u16>("10cm").unwrap(), 10);
+        assert_eq!(parse_distance::<u16>("3dm").unwrap(), 30);
+        assert_eq!(parse_distance::<u16>("55\r\n").unwrap(), 55);
+        assert_eq!(parse_distance::<i16>("-20cm").unwrap(), -20);
+
+        assert!(matches!(parse_distance::<u16>("-20cm"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_distance::<u8>("300cm"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_distance::<u16>("10km"), Err(TelloError::ParseError { .. })));
+        assert!(matches!(parse_distance::<u16>("error"), Err(TelloError::ParseError { .. })));
+    }
+
+    #[test]
+    fn not_too_close() {
+        assert!(check_not_too_close(20, 0, 0).is_ok());
+        assert!(check_not_too_close(0, -20, 0).is_ok());
+        assert!(check_not_too_close(0, 0, 20).is_ok());
+        assert!(check_not_too_close(19, -19, 20).is_ok());
+
+        assert!(matches!(check_not_too_close(19, 0, 0), Err(TelloError::OutOfRange)));
+        assert!(matches!(check_not_too_close(19, -19, 19), Err(TelloError::OutOfRange)));
+        assert!(matches!(check_not_too_close(0, 0, 0), Err(TelloError::OutOfRange)));
+    }
+
+    #[test]
+    fn curves() {
+        assert_eq!(curve_command((20, 20, 0), (40, 60, 0), 30), "curve 20 20 0 40 60 0 30");
+
+        // no arc through the origin and both points
+        assert!(in_line((20, 20, 0), (40, 40, 0)));
+        assert!(in_line((20, -20, 10), (-40, 40, -20)));
+        assert!(in_line((0, 0, 0), (40, 60, 0)));
+        assert!(!in_line((20, 20, 0), (40, 60, 0)));
+        assert!(!in_line((500, 0, 0), (500, 1, 0)));
+    }
+
+    #[test]
+    fn jumps() {
+        assert_eq!(jump_command((0, 0, 100), 50, 90, (1, 2)), "jump 0 0 100 50 90 m1 m2");
+        assert_eq!(jump_command((-500, 500, -20), 10, -180, (8, 8)), "jump -500 500 -20 10 -180 m8 m8");
+    }
+
+    #[test]
+    fn credentials() {
+        assert!(check_credential("SSID", "TELLO-1234").is_ok());
+        assert!(check_credential("password", "p@ss!w0rd~").is_ok());
+
+        assert!(check_credential("SSID", "").is_err());
+        assert!(check_credential("SSID", "my network").is_err());
+        assert!(check_credential("password", "tab\there").is_err());
+        assert!(check_credential("password", "café").is_err());
+        assert!(check_credential("password", "密码").is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_curves_and_jumps_are_rejected_without_sending() {
+        let mut mock = MockTello::start(ok).await;
+        let drone = mock.connect().await;
+
+        assert!(matches!(drone.curve(20, 20, 0, 40, 40, 0, 30).await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.curve(20, 20, 0, 40, 60, 0, 61).await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.jump(0, 0, 100, 50, 90, 0, 2).await, Err(TelloError::OutOfRange)));
+        assert!(matches!(drone.jump(0, 0, 501, 50, 90, 1, 2).await, Err(TelloError::OutOfRange)));
+        sleep(Duration::from_millis(50)).await;
+        assert!(mock.received_nothing_more());
+    }
+
+    #[tokio::test]
+    async fn hardware_is_queried() {
+        let mut mock = MockTello::start(responding(&[("hardware?", &["RMTT\r\n"])])).await;
+        let drone = mock.connect().await;
+
+        assert_eq!(drone.hardware().await.unwrap(), "RMTT");
+        assert_eq!(mock.next_command().await, "hardware?");
+    }
+
+    #[tokio::test]
+    async fn hardware_times_out_on_older_firmware() {
+        let mut mock = MockTello::start(responding(&[("hardware?", &[])])).await;
+        let mut options = mock.options();
+        options.command_timeout = Duration::from_millis(100);
+        let drone = mock.connect_with(options).await;
+
+        assert!(matches!(drone.hardware().await, Err(TelloError::Timeout { command }) if command == "hardware?"));
+    }
+
+    #[tokio::test]
+    async fn barometer_is_in_metres_with_or_without_the_unit() {
+        let mut mock = MockTello::start(responding(&[("baro?", &["-57.14\r\n"])])).await;
+        let drone = mock.connect().await;
+        assert_eq!(drone.barometer().await.unwrap(), -57.14);
+
+        let mut mock = MockTello::start(responding(&[("baro?", &["12.5m\r\n"])])).await;
+        let drone = mock.connect().await;
+        assert_eq!(drone.barometer().await.unwrap(), 12.5);
+    }
+
+    #[tokio::test]
+    async fn firmware_version_is_queried() {
+        let mut mock = MockTello::start(responding(&[("firmware?", &["01.04.92.01\r\n"])])).await;
+        let drone = mock.connect().await;
+
+        assert_eq!(drone.firmware_version().await.unwrap(), "01.04.92.01");
+        assert_eq!(mock.next_command().await, "firmware?");
+    }
+
+    #[tokio::test]
+    async fn firmware_version_times_out_on_older_firmware() {
+        let mut mock = MockTello::start(responding(&[("firmware?", &[])])).await;
+        let mut options = mock.options();
+        options.command_timeout = Duration::from_millis(100);
+        let drone = mock.connect_with(options).await;
+
+        assert!(matches!(drone.firmware_version().await, Err(TelloError::Timeout { command }) if command == "firmware?"));
+    }
+
+    #[tokio::test]
+    async fn moves_send_their_keywords() {
+        let mut mock = MockTello::start(ok).await;
+        let drone = mock.connect().await;
+
+        for (direction, keyword) in [
+            (Direction::Up, "up"), (Direction::Down, "down"), 
+            (Direction::Left, "left"), (Direction::Right, "right"), 
+            (Direction::Forward, "forward"), (Direction::Back, "back")
+        ] {
+            drone.move_in(direction, 50).await.unwrap();
+            assert_eq!(mock.next_command().await, format!("{keyword} 50"));
+        }
+
+        drone.move_up(20).await.unwrap();
+        drone.move_down(30).await.unwrap();
+        drone.move_left(40).await.unwrap();
+        drone.move_right(50).await.unwrap();
+        drone.move_forward(60).await.unwrap();
+        drone.move_back(500).await.unwrap();
+        for expected in ["up 20", "down 30", "left 40", "right 50", "forward 60", "back 500"] {
+            assert_eq!(mock.next_command().await, expected);
+        }
+
+        let timeout = Duration::from_secs(1);
+        drone.move_up_timeout(20, timeout).await.unwrap();
+        drone.move_down_timeout(30, timeout).await.unwrap();
+        drone.move_left_timeout(40, timeout).await.unwrap();
+        drone.move_right_timeout(50, timeout).await.unwrap();
+        drone.move_forward_timeout(60, timeout).await.unwrap();
+        drone.move_back_timeout(70, timeout).await.unwrap();
+        for expected in ["up 20", "down 30", "left 40", "right 50", "forward 60", "back 70"] {
+            assert_eq!(mock.next_command().await, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn moves_update_the_position_estimate() {
+        let mut mock = MockTello::start(ok).await;
+        let drone = mock.connect().await;
+
+        drone.move_in(Direction::Forward, 100).await.unwrap();
+        drone.move_in(Direction::Left, 50).await.unwrap();
+        drone.move_in(Direction::Up, 30).await.unwrap();
+
+        let position = *drone.inner.position.lock().unwrap();
+        assert_eq!((position.x, position.y, position.z), (100.0, 50.0, 30.0));
+    }
+
+    #[tokio::test]
+    async fn reconnecting_straight_after_disconnecting_rebinds_the_listeners() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.state_port = free_local_port().await;
+        options.video_port = free_local_port().await;
+        let _state_receiver = options.with_state();
+        let _video_receiver = options.with_video();
+        let mut drone = mock.connect_with(options).await;
+
+        for _ in 0..2 {
+            let (disconnected, options) = drone.disconnect_keeping_options().await.unwrap();
+            drone = disconnected.connect_with(options).await.unwrap();
+        }
+        assert!(drone.inner.state_listener.lock().await.is_some());
+        assert!(drone.inner.video_listener.lock().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn restarting_video_again_and_again_rebinds_the_listeners() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.state_port = free_local_port().await;
+        options.video_port = free_local_port().await;
+        let mut state_receiver = options.with_state();
+        let mut video_receiver = options.with_video();
+        let (state_port, video_port) = (options.state_port, options.video_port);
+        let drone = mock.connect_with(options).await;
+
+        for _ in 0..5 {
+            drone.restart_listeners().await.unwrap();
+            assert_eq!(mock.next_command().await, "streamon");
+            drone.stop_video().await.unwrap();
+            assert_eq!(mock.next_command().await, "streamoff");
+        }
+
+        // still listening on the same ports
+        let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sock.send_to(b"pitch:0;roll:0;yaw:0;h:0;bat:80;", ("127.0.0.1", state_port)).await.unwrap();
+        sock.send_to(&[0, 0, 0, 1, 0x65, 1, 2, 3], ("127.0.0.1", video_port)).await.unwrap();
+        let state = tokio::time::timeout(Duration::from_secs(1), state_receiver.recv()).await.unwrap().unwrap();
+        assert_eq!(state.battery, 80);
+        let frame = tokio::time::timeout(Duration::from_secs(1), video_receiver.recv()).await.unwrap().unwrap();
+        assert_eq!(frame.data, [0, 0, 0, 1, 0x65, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn take_off_reporting_waits_for_the_height_to_settle() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.state_port = free_local_port().await;
+        let _state_receiver = options.with_state();
+        let state_port = options.state_port;
+        let drone = mock.connect_with(options).await;
+
+        // climbs, then hovers
+        let state = tokio::spawn(async move {
+            let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            for height in (0..=80).step_by(10).chain(std::iter::repeat(81)) {
+                let message = format!("pitch:0;roll:0;yaw:0;h:{height};bat:80;");
+                sock.send_to(message.as_bytes(), ("127.0.0.1", state_port)).await.unwrap();
+                sleep(TAKE_OFF_POLL_INTERVAL).await;
+            }
+        });
+
+        let start = tokio::time::Instant::now();
+        let height = drone.take_off_reporting().await.unwrap();
+        state.abort();
+        assert_eq!(mock.next_command().await, "takeoff");
+        assert_eq!(height, 81);
+        assert!(start.elapsed() < TAKE_OFF_SETTLE_TIMEOUT, "gave up rather than settling");
+    }
+
+    #[tokio::test]
+    async fn concurrent_commands_get_their_own_responses() {
+        let mut mock = MockTello::start(|command: &str| match command.strip_suffix('?') {
+            Some(query) if query.starts_with("query") => vec![format!("answer{}\r\n", &query["query".len()..])],
+            _ => ok(command)
+        }).await;
+        let drone = Arc::new(mock.connect().await);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in 0..50 {
+            let drone = drone.clone();
+            tasks.spawn(async move {
+                assert_eq!(drone.battery().await.unwrap(), 87);
+                assert_eq!(drone.send(&format!("query{i}?")).await.unwrap(), format!("answer{i}"));
+            });
+        }
+        while let Some(task) = tasks.join_next().await {
+            task.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn dropping_while_flying_stops_the_drone_even_after_a_panic() {
+        let mut mock = MockTello::start(ok).await;
+        let drone = mock.connect().await;
+        drone.take_off().await.unwrap();
+        assert_eq!(mock.next_command().await, "takeoff");
+
+        // poison the flight status
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _flight_status = drone.inner.flight_status.lock().unwrap();
+            panic!("while holding the flight status");
+        }));
+        assert!(drone.inner.flight_status.is_poisoned());
+
+        drop(drone);
+        assert_eq!(mock.next_command().await, "rc 0 0 0 0");
+    }
+
+    #[tokio::test]
+    async fn dropping_releases_the_listener_ports() {
+        let mut mock = MockTello::start(ok).await;
+        let mut options = mock.options();
+        options.state_port = free_local_port().await;
+        options.video_port = free_local_port().await;
+        let _state_receiver = options.with_state();
+        let _video_receiver = options.with_video();
+        let (state_port, video_port) = (options.state_port, options.video_port);
+        let drone = mock.connect_with(options).await;
+
+        drop(drone);
+        sleep(Duration::from_millis(50)).await;
+        UdpSocket::bind(("127.0.0.1", state_port)).await.unwrap();
+        UdpSocket::bind(("127.0.0.1", video_port)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failing_to_connect_releases_the_listener_ports() {
+        let mock = MockTello::start(responding(&[("sdk?", &["20\r\n"])])).await;
+        let mut options = mock.options();
+        options.state_port = free_local_port().await;
+        options.require_sdk_version = Some((30, 0));
+        let _state_receiver = options.with_state();
+        let state_port = options.state_port;
+
+        let result = Tello::new().assume_wifi().await.unwrap().connect_with(options).await;
+        assert!(matches!(result, Err(TelloError::UnsupportedFirmware { .. })));
+        sleep(Duration::from_millis(50)).await;
+        UdpSocket::bind(("127.0.0.1", state_port)).await.unwrap();
+    }
+}
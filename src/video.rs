@@ -1,68 +1,466 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::{spawn, task};
 use tokio::sync::mpsc;
 use tokio::net::UdpSocket;
 use bytebuffer::ByteBuffer;
+use socket2::{Socket, Domain, Type, Protocol};
+use log::{debug, info, warn};
 
-use crate::errors::Result;
+use crate::errors::{Result, TelloError};
+use crate::recv_backoff::RecvBackoff;
 
 pub const VIDEO_WIDTH:u32 = 960;
 pub const VIDEO_HEIGHT:u32 = 720; 
 
-const VIDEO_UDP_PORT:u32 = 11111;
+pub(crate) const DEFAULT_VIDEO_UDP_PORT:u16 = 11111;
 const MAX_CHUNK_SIZE:usize = 1460;
 
+/// Time over which the frame rate is measured.
+const STATS_WINDOW:Duration = Duration::from_secs(2);
 
-pub type TelloVideoSender = mpsc::UnboundedSender<TelloVideoFrame>;
-pub type TelloVideoReceiver = mpsc::UnboundedReceiver<TelloVideoFrame>;
 
-pub fn make_tello_video_channel() -> (TelloVideoSender, TelloVideoReceiver) {
-    mpsc::unbounded_channel()
+/// Sender end of the channel video frames are passed on through.
+#[derive(Debug, Clone)]
+pub enum TelloVideoSender {
+    Unbounded(mpsc::UnboundedSender<TelloVideoFrame>),
+    Bounded(mpsc::Sender<TelloVideoFrame>)
+}
+
+/// Receiver end of the channel video frames are passed on through, see 
+/// `TelloOptions::with_video()`.
+#[derive(Debug)]
+pub enum TelloVideoReceiver {
+    Unbounded(mpsc::UnboundedReceiver<TelloVideoFrame>),
+    Bounded(mpsc::Receiver<TelloVideoFrame>)
+}
+
+/// Makes a video channel, bounded if a capacity is given.
+pub fn make_tello_video_channel(capacity: Option<usize>) -> (TelloVideoSender, TelloVideoReceiver) {
+    match capacity {
+        Some(capacity) => {
+            let (tx, rx) = mpsc::channel(capacity);
+            (TelloVideoSender::Bounded(tx), TelloVideoReceiver::Bounded(rx))
+        }
+        None => {
+            let (tx, rx) = mpsc::unbounded_channel();
+            (TelloVideoSender::Unbounded(tx), TelloVideoReceiver::Unbounded(rx))
+        }
+    }
+}
+
+impl TelloVideoSender {
+    /// Passes on a frame without waiting, dropping it if a bounded channel 
+    /// is full. Fails if the receiver has been dropped.
+    pub(crate) fn send(&self, frame: TelloVideoFrame) -> std::result::Result<(), mpsc::error::SendError<TelloVideoFrame>> {
+        match self {
+            TelloVideoSender::Unbounded(tx) => tx.send(frame),
+            TelloVideoSender::Bounded(tx) => match tx.try_send(frame) {
+                Ok(()) => Ok(()),
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    warn!("[Video] WARNING video channel is full, frame dropped");
+                    Ok(())
+                }
+                Err(mpsc::error::TrySendError::Closed(frame)) => Err(mpsc::error::SendError(frame))
+            }
+        }
+    }
+}
+
+impl TelloVideoReceiver {
+    /// Receives the next frame, or `None` if the channel has closed.
+    pub async fn recv(&mut self) -> Option<TelloVideoFrame> {
+        match self {
+            TelloVideoReceiver::Unbounded(rx) => rx.recv().await,
+            TelloVideoReceiver::Bounded(rx) => rx.recv().await
+        }
+    }
+
+    /// Receives the next frame, blocking the thread, eg in a GUI loop 
+    /// outside the async runtime.
+    pub fn blocking_recv(&mut self) -> Option<TelloVideoFrame> {
+        match self {
+            TelloVideoReceiver::Unbounded(rx) => rx.blocking_recv(),
+            TelloVideoReceiver::Bounded(rx) => rx.blocking_recv()
+        }
+    }
+
+    /// Receives the next frame if there is one waiting.
+    pub fn try_recv(&mut self) -> std::result::Result<TelloVideoFrame, mpsc::error::TryRecvError> {
+        match self {
+            TelloVideoReceiver::Unbounded(rx) => rx.try_recv(),
+            TelloVideoReceiver::Bounded(rx) => rx.try_recv()
+        }
+    }
+}
+
+/// The video stream bitrate, see `Tello::set_video_bitrate()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VideoBitrate {
+    /// Chosen by the drone, the default.
+    #[default]
+    Auto,
+    Mbps1,
+    Mbps2,
+    Mbps3,
+    Mbps4,
+    Mbps5
+}
+
+impl VideoBitrate {
+    /// The value for the `setbitrate` command.
+    pub(crate) fn value(&self) -> u8 {
+        match self {
+            VideoBitrate::Auto => 0,
+            VideoBitrate::Mbps1 => 1,
+            VideoBitrate::Mbps2 => 2,
+            VideoBitrate::Mbps3 => 3,
+            VideoBitrate::Mbps4 => 4,
+            VideoBitrate::Mbps5 => 5
+        }
+    }
+}
+
+/// The video stream resolution, see `Tello::set_video_resolution()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VideoResolution {
+    /// 480p
+    Low,
+
+    /// 720p, the default.
+    #[default]
+    High
+}
+
+impl VideoResolution {
+    /// The value for the `setresolution` command.
+    pub(crate) fn value(&self) -> &'static str {
+        match self {
+            VideoResolution::Low => "low",
+            VideoResolution::High => "high"
+        }
+    }
+}
+
+/// The video stream frame rate, see `Tello::set_video_fps()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFps {
+    /// 5 frames per second.
+    Low,
+
+    /// 15 frames per second.
+    Middle,
+
+    /// 30 frames per second, the default.
+    #[default]
+    High
+}
+
+impl VideoFps {
+    /// The value for the `setfps` command.
+    pub(crate) fn value(&self) -> &'static str {
+        match self {
+            VideoFps::Low => "low",
+            VideoFps::Middle => "middle",
+            VideoFps::High => "high"
+        }
+    }
 }
 
 /// A frame of video from the drone.
 #[derive(Debug)]
 pub struct TelloVideoFrame {
-    pub data: Vec<u8>
+    pub data: Vec<u8>,
+
+    /// Counts up from 0 with each frame passed on, so a gap means frames 
+    /// were dropped, eg because the channel was full.
+    pub sequence: u64,
+
+    /// When the last of the frame's data arrived.
+    pub received_at: Instant
+}
+
+/// Statistics for the received video stream, see `Tello::video_stats()`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VideoStats {
+    /// Frames received per second, averaged over the last 2 seconds.
+    pub frames_per_second: f32,
+
+    /// Total frames received.
+    pub total_frames: u64,
+
+    /// Total frames dropped as incomplete.
+    pub dropped_frames: u64
+}
+
+/// Keeps track of the video stream statistics.
+#[derive(Debug, Default)]
+pub(crate) struct VideoStatsTracker {
+    frame_times: VecDeque<Instant>,
+    total_frames: u64,
+    dropped_frames: u64
 }
 
+impl VideoStatsTracker {
+    fn frame_received(&mut self) {
+        let now = Instant::now();
+        self.frame_times.push_back(now);
+        while self.frame_times.front().is_some_and(|t| now.duration_since(*t) > STATS_WINDOW) {
+            self.frame_times.pop_front();
+        }
+        self.total_frames += 1;
+    }
+
+    fn frame_dropped(&mut self) {
+        self.dropped_frames += 1;
+    }
+
+    pub(crate) fn stats(&self) -> VideoStats {
+        let now = Instant::now();
+        let recent_frames = self.frame_times.iter().filter(|t| now.duration_since(**t) <= STATS_WINDOW).count();
+        VideoStats {
+            frames_per_second: recent_frames as f32 / STATS_WINDOW.as_secs_f32(),
+            total_frames: self.total_frames,
+            dropped_frames: self.dropped_frames
+        }
+    }
+}
+
+pub(crate) type SharedVideoStats = Arc<Mutex<VideoStatsTracker>>;
+
 #[derive(Debug)]
 pub(crate) struct VideoListener {
-    task: task::JoinHandle<()>
+    task: task::JoinHandle<()>,
+    pub(crate) local_address: SocketAddr,
+    pub(crate) sender: TelloVideoSender
 }   
 
 impl VideoListener {
-    pub(crate) async fn start_listening(sender:TelloVideoSender) -> Result<Self> { 
-        let local_address = format!("0.0.0.0:{VIDEO_UDP_PORT}");
-        println!("[Video] START LISTENING at {local_address}");
+    pub(crate) async fn start_listening(local_address:SocketAddr, sender:TelloVideoSender, stats:SharedVideoStats, receive_buffer_size:Option<usize>) -> Result<Self> { 
+        info!("[Video] START LISTENING at {local_address}");
 
-        let sock = UdpSocket::bind(&local_address).await?;
+        let sock = match receive_buffer_size {
+            Some(size) => bind_with_receive_buffer_size(local_address, size)?,
+            None => UdpSocket::bind(&local_address).await?
+        };
 
+        let frame_sender = sender.clone();
         let task = spawn(async move {
-            let mut buf = ByteBuffer::new();
+            let sender = frame_sender;
+            let mut assembler = FrameAssembler::default();
+            let mut sequence = 0;
+            let mut backoff = RecvBackoff::default();
             loop {
                 let s = &sock;
-                let mut chunk = vec![0; MAX_CHUNK_SIZE]; //Vec::with_capacity(MAX_CHUNK_SIZE);        
-                let n = s.recv(&mut chunk).await.unwrap();
-                if n != 0 {
-                    buf.write_bytes(&chunk);
-
-                    if n < MAX_CHUNK_SIZE {
-                        let frame = TelloVideoFrame { data: buf.into_vec() };
-                        sender.send(frame).unwrap();
-                        buf = ByteBuffer::new();
+                let mut chunk = vec![0; MAX_CHUNK_SIZE];
+                let n = match s.recv(&mut chunk).await {
+                    Ok(n) => n,
+                    Err(err) => {
+                        if backoff.failed() {
+                            warn!("[Video] failed to receive ({err}), {} time(s) in a row", backoff.failures());
+                        }
+                        backoff.wait().await;
+                        continue;
+                    }
+                };
+                backoff.succeeded();
+
+                for data in assembler.push(&chunk[..n]) {
+                    if !has_nal_start_code(&data) {
+                        debug!("[Video] suspicious frame ({} bytes, no NAL start code), dropped", data.len());
+                        stats.lock().unwrap().frame_dropped();
+                        continue;
+                    }
+
+                    stats.lock().unwrap().frame_received();
+
+                    let frame = TelloVideoFrame { data, sequence, received_at: Instant::now() };
+                    sequence += 1;
+                    if sender.send(frame).is_err() {
+                        info!("[Video] receiver dropped, stopped receiving video");
+                        return;
                     }
                 }
             }
         });
 
-        Ok(Self { task })
+        Ok(Self { task, local_address, sender })
+    }
+
+    /// Stops without waiting, eg when the drone is dropped. The socket is
+    /// released once the runtime next polls the aborted task.
+    pub(crate) fn abort(&self) {
+        self.task.abort();
     }
 
-    pub(crate) async fn stop_listening(&self) -> Result<()> {
-        println!("[Video] STOP LISTENING");
+    /// Stops listening, only returning once the socket has been released.
+    pub(crate) async fn stop_listening(self) -> Result<()> {
+        info!("[Video] STOP LISTENING");
         self.task.abort();
-        // TODO?
-        // let _err = self.task.await;
-        Ok(())
+        match self.task.await {
+            Err(err) if err.is_panic() => Err(TelloError::Generic { msg: format!("video listener failed - {err}") }),
+            _ => Ok(())
+        }
     }
  }
+
+/// Saves the raw h264 video to a file, eg `flight.h264`, until the channel
+/// closes. The file can be played with eg VLC, or converted with ffmpeg.
+///
+/// - `video_receiver` From `TelloOptions::with_video()`
+/// - `path` The file to write
+///
+pub async fn record_h264<P: AsRef<Path>>(mut video_receiver: TelloVideoReceiver, path: P) -> Result<()> {
+    let path = path.as_ref();
+    info!("[Video] RECORDING to {}", path.display());
+
+    // the frames are already an Annex-B byte stream, so just need joining up
+    let mut file = File::create(path)?;
+    while let Some(frame) = video_receiver.recv().await {
+        file.write_all(&frame.data)?;
+        file.flush()?;
+    }
+
+    info!("[Video] RECORDING ended");
+    Ok(())
+}
+
+/// Assembles frames from the chunks of video data as they are received.
+///
+/// The drone splits each frame into full size chunks followed by a shorter
+/// one, so a short chunk normally ends a frame. A frame can also end with a
+/// full chunk, in which case it ends when the next chunk starts a new NAL 
+/// unit.
+#[derive(Debug)]
+struct FrameAssembler {
+    buf: ByteBuffer
+}
+
+impl Default for FrameAssembler {
+    fn default() -> Self {
+        Self { buf: ByteBuffer::new() }
+    }
+}
+
+impl FrameAssembler {
+    /// Adds a chunk, returning any frames it completes.
+    fn push(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = vec![];
+        if chunk.is_empty() {
+            return frames;
+        }
+
+        // previous frame ended exactly on a full chunk?
+        if !self.buf.is_empty() && has_nal_start_code(chunk) {
+            frames.push(self.take());
+        }
+
+        self.buf.write_bytes(chunk);
+
+        if chunk.len() < MAX_CHUNK_SIZE {
+            frames.push(self.take());
+        }
+
+        frames
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::replace(&mut self.buf, ByteBuffer::new()).into_vec()
+    }
+}
+
+/// Binds a UDP socket with the OS receive buffer (`SO_RCVBUF`) set to the 
+/// given size.
+fn bind_with_receive_buffer_size(local_address:SocketAddr, size:usize) -> Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_recv_buffer_size(size)?;
+
+    let actual_size = socket.recv_buffer_size()?;
+    if actual_size < size {
+        warn!("[Video] WARNING receive buffer is {actual_size} bytes, not the {size} requested (check OS limits)");
+    }
+
+    socket.set_nonblocking(true)?;
+    socket.bind(&local_address.into())?;
+
+    Ok(UdpSocket::from_std(socket.into())?)
+}
+
+/// Checks that the data starts with an h264 Annex-B NAL unit start code, 
+/// either `00 00 00 01` or the short form `00 00 01`.
+fn has_nal_start_code(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 0, 1]) || data.starts_with(&[0, 0, 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chunk of video data of the given size, starting a NAL unit or not.
+    fn chunk(size: usize, starts_nal_unit: bool) -> Vec<u8> {
+        let mut chunk = vec![0xAA; size];
+        if starts_nal_unit {
+            chunk[..4].copy_from_slice(&[0, 0, 0, 1]);
+        }
+        chunk
+    }
+
+    #[test]
+    fn short_chunk_ends_a_frame() {
+        let mut assembler = FrameAssembler::default();
+
+        assert!(assembler.push(&chunk(MAX_CHUNK_SIZE, true)).is_empty());
+        assert!(assembler.push(&chunk(MAX_CHUNK_SIZE, false)).is_empty());
+        let frames = assembler.push(&chunk(100, false));
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), 2 * MAX_CHUNK_SIZE + 100);
+        assert!(has_nal_start_code(&frames[0]));
+
+        // a frame in a single short chunk
+        let frames = assembler.push(&chunk(500, true));
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), 500);
+    }
+
+    #[test]
+    fn nal_start_code_ends_a_frame_of_full_chunks() {
+        let mut assembler = FrameAssembler::default();
+
+        assert!(assembler.push(&chunk(MAX_CHUNK_SIZE, true)).is_empty());
+        assert!(assembler.push(&chunk(MAX_CHUNK_SIZE, false)).is_empty());
+
+        // the next frame starts, so the last one ended on a full chunk
+        let frames = assembler.push(&chunk(MAX_CHUNK_SIZE, true));
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), 2 * MAX_CHUNK_SIZE);
+
+        // and both rules at once - a short chunk starting a new frame ends 
+        // the previous one and itself
+        let frames = assembler.push(&chunk(200, true));
+        assert_eq!(frames.iter().map(Vec::len).collect::<Vec<_>>(), [MAX_CHUNK_SIZE, 200]);
+    }
+
+    #[test]
+    fn empty_chunks_are_ignored() {
+        let mut assembler = FrameAssembler::default();
+
+        assert!(assembler.push(&[]).is_empty());
+        assert!(assembler.push(&chunk(MAX_CHUNK_SIZE, true)).is_empty());
+        assert!(assembler.push(&[]).is_empty());
+        assert_eq!(assembler.push(&chunk(10, false)).len(), 1);
+    }
+
+    #[test]
+    fn nal_start_codes() {
+        assert!(has_nal_start_code(&[0, 0, 0, 1, 0x67]));
+        assert!(has_nal_start_code(&[0, 0, 1, 0x67]));
+        assert!(!has_nal_start_code(&[0, 1, 0x67]));
+        assert!(!has_nal_start_code(&[0xAA, 0, 0, 1]));
+        assert!(!has_nal_start_code(&[]));
+    }
+}
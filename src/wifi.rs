@@ -1,5 +1,10 @@
-use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::{spawn, task};
+use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
+use log::{info, warn};
 
 use crate::{TelloError, Result}; 
 
@@ -7,8 +12,8 @@ use crate::{TelloError, Result};
 // macOS
 
 #[cfg(target_os = "macos")]
-fn list_wifi_devices() -> Result<Vec<String>> {
-    let output = run_command("networksetup", &["-listallhardwareports"])?;
+async fn list_wifi_devices() -> Result<Vec<String>> {
+    let output = run_command("networksetup", &["-listallhardwareports"]).await?;
 
     let mut found_wifi = false;
     let mut devices:Vec<String> = vec![];
@@ -30,55 +35,214 @@ fn list_wifi_devices() -> Result<Vec<String>> {
 }
 
 #[cfg(target_os = "macos")]
-pub async fn wait_for_wifi(ssid_prefix: &str) -> Result<()> {
-    let devices = list_wifi_devices()?;
-
-    // wait for any one of them to connect
-    let waiting_for = format!("Current Wi-Fi Network: {ssid_prefix}");
-    loop {
-        for device in devices.iter() {
-            let s = run_command("networksetup", &["-getairportnetwork", device])?;
-            if s.starts_with(&waiting_for) {
-                return Ok(())
-
-            }
+async fn current_ssids() -> Result<Vec<String>> {
+    let prefix = "Current Wi-Fi Network: ";
+    let mut ssids = vec![];
+    for device in list_wifi_devices().await? {
+        let s = run_command("networksetup", &["-getairportnetwork", &device]).await?;
+        if let Some(ssid) = s.strip_prefix(prefix).map(|s| s.trim()) {
+            ssids.push(ssid.to_string());
         }
-        sleep(Duration::from_millis(100)).await;
     }
+    Ok(ssids)
 }
 
 //////////////////////////////////////////////////////////////////////////////
 // linux
 
 #[cfg(target_os = "linux")]
-pub async fn wait_for_wifi(ssid_prefix: &str) -> Result<()> {
-    loop {
-        let s = run_command("iwgetid", &["-r"])?;
-        if s.starts_with(ssid_prefix) {
-            return Ok(())
+async fn current_ssids() -> Result<Vec<String>> {
+    let s = run_command("iwgetid", &["-r"]).await?;
+    let ssid = s.trim();
+    Ok(if ssid.is_empty() { vec![] } else { vec![ssid.to_string()] })
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// windows
+
+#[cfg(target_os = "windows")]
+async fn current_ssids() -> Result<Vec<String>> {
+    let s = run_command("netsh", &["wlan", "show", "interfaces"]).await?;
+
+    // the output is localized, but the keys used here are not, eg
+    //     GUID                   : 12345678-...
+    //     SSID                   : TELLO-ABC123
+    let values:Vec<(&str, &str)> = s.lines()
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect();
+
+    if !values.iter().any(|(k, _)| *k == "GUID") {
+        return Err(TelloError::WiFiNotConnected);
+    }
+
+    Ok(values.iter().filter(|(k, _)| *k == "SSID").map(|(_, ssid)| ssid.to_string()).collect())
+}
+
+//////////////////////////////////////////////////////////////////////////////
+// supported
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+pub async fn wait_for_wifi<F: Fn(&str) -> bool>(is_drone_ssid: F, timeout: Option<Duration>) -> Result<Option<String>> {
+    wait_for_ssid(current_ssids, is_drone_ssid, timeout).await.map(Some)
+}
+
+/// Lists the networks joined until one is the drone's, giving up with 
+/// `TelloError::Timeout` once the timeout is up, even in the middle of 
+/// listing them.
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+async fn wait_for_ssid<L, LF, F>(list_ssids: L, is_drone_ssid: F, timeout: Option<Duration>) -> Result<String> 
+where 
+    L: Fn() -> LF, 
+    LF: std::future::Future<Output = Result<Vec<String>>>, 
+    F: Fn(&str) -> bool 
+{
+    let wait = async {
+        loop {
+            if let Some(ssid) = list_ssids().await?.into_iter().find(|ssid| is_drone_ssid(ssid)) {
+                return Ok(ssid)
+            }
+            sleep(Duration::from_millis(100)).await;
         }
-        sleep(Duration::from_millis(100)).await;
+    };
+
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, wait).await
+            .map_err(|_| TelloError::Timeout { command: "wait for WiFi".to_string() })?,
+        None => wait.await
     }
 }
 
 //////////////////////////////////////////////////////////////////////////////
 // anything else
 
-#[cfg(not(any(target_os = "macos", target_os = "linux")))]
-pub async fn wait_for_wifi(ssid_prefix: &str) -> Result<()> {
-    println!("[WiFi] warning - wait_for_wifi has not been implemented for this OS, assuming joined already and continuing");
-    Ok(())
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub async fn wait_for_wifi<F: Fn(&str) -> bool>(_is_drone_ssid: F, _timeout: Option<Duration>) -> Result<Option<String>> {
+    warn!("[WiFi] warning - wait_for_wifi has not been implemented for this OS, assuming joined already and continuing");
+    Ok(None)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+async fn current_ssids() -> Result<Vec<String>> {
+    Err(TelloError::Generic { msg: "WiFi networks can't be listed on this OS".to_string() })
 }
 
 //////////////////////////////////////////////////////////////////////////////
 
-fn run_command(cmd:&str, args: &[&str]) -> Result<String> {
+/// A change in the WiFi connection to the drone, see 
+/// `TelloOptions::with_wifi_monitor()`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WiFiEvent {
+    /// The host has left the drone's network.
+    Lost { ssid: String },
+
+    /// The host has joined the drone's network again.
+    Rejoined { ssid: String }
+}
+
+pub type WiFiEventSender = mpsc::UnboundedSender<WiFiEvent>;
+pub type WiFiEventReceiver = mpsc::UnboundedReceiver<WiFiEvent>;
+
+pub fn make_wifi_event_channel() -> (WiFiEventSender, WiFiEventReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// How often the WiFi network is checked while connected.
+const WIFI_MONITOR_INTERVAL:Duration = Duration::from_secs(1);
+
+/// Watches that the host stays on the drone's WiFi network while 
+/// connected, setting `lost` and passing on events when it leaves or 
+/// rejoins.
+#[derive(Debug)]
+pub(crate) struct WiFiMonitor {
+    task: task::JoinHandle<()>
+}
+
+impl WiFiMonitor {
+    pub(crate) fn start(ssid: String, lost: Arc<AtomicBool>, sender: WiFiEventSender) -> Self {
+        info!("[WiFi] START MONITORING {ssid}");
+
+        let task = spawn(async move {
+            loop {
+                sleep(WIFI_MONITOR_INTERVAL).await;
+
+                let joined = current_ssids().await.is_ok_and(|ssids| ssids.contains(&ssid));
+                let was_lost = lost.swap(!joined, Ordering::Relaxed);
+
+                let event = match (was_lost, joined) {
+                    (false, false) => {
+                        warn!("[WiFi] WARNING lost WiFi {ssid}");
+                        WiFiEvent::Lost { ssid: ssid.clone() }
+                    }
+                    (true, true) => {
+                        info!("[WiFi] rejoined WiFi {ssid}");
+                        WiFiEvent::Rejoined { ssid: ssid.clone() }
+                    }
+                    _ => continue
+                };
+
+                // carry on without a receiver, as `lost` is still needed 
+                // to blame errors on the WiFi
+                let _ = sender.send(event);
+            }
+        });
+
+        Self { task }
+    }
+
+    /// Stops without waiting, eg when the drone is dropped.
+    pub(crate) fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub(crate) async fn stop(self) {
+        info!("[WiFi] STOP MONITORING");
+        self.task.abort();
+        let _ = self.task.await;
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+async fn run_command(cmd:&str, args: &[&str]) -> Result<String> {
     let raw_output = Command::new(cmd)
         .args(args)
+        // don't leave it running if given up on, eg by `wait_for_wifi`
+        .kill_on_drop(true)
         .output()
-        .expect("failed to run {cmd}");
+        .await
+        .map_err(|e| TelloError::Generic { msg: format!("failed to run {cmd} - {e}") })?;
 
     String::from_utf8(raw_output.stdout).map_err(
         |e|  TelloError::Generic { msg: format!("failed to decode {cmd} output - {e:?}") }
     )
 }
+
+#[cfg(all(test, any(target_os = "linux", target_os = "macos")))]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn waiting_for_wifi_finds_the_drone_network() {
+        let list_ssids = || async { Ok(vec!["home".to_string(), "TELLO-ABC123".to_string()]) };
+        let ssid = wait_for_ssid(list_ssids, |ssid| ssid.starts_with("TELLO"), Some(Duration::from_secs(1))).await;
+        assert_eq!(ssid.unwrap(), "TELLO-ABC123");
+    }
+
+    #[tokio::test]
+    async fn waiting_for_wifi_times_out_while_listing_hangs() {
+        let list_ssids = || async { run_command("sleep", &["5"]).await.map(|_| vec![]) };
+        let started = Instant::now();
+        let ssid = wait_for_ssid(list_ssids, |_| true, Some(Duration::from_millis(200))).await;
+        assert!(matches!(ssid, Err(TelloError::Timeout { .. })));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn waiting_for_wifi_times_out_without_the_drone_network() {
+        let list_ssids = || async { Ok(vec!["home".to_string()]) };
+        let ssid = wait_for_ssid(list_ssids, |ssid| ssid.starts_with("TELLO"), Some(Duration::from_millis(300))).await;
+        assert!(matches!(ssid, Err(TelloError::Timeout { .. })));
+    }
+}